@@ -0,0 +1,139 @@
+use crate::{
+    config::{AnimationConfig, LoopMode, SpriteSheetConfig},
+    texture::Texture,
+};
+
+/// Sequence of textures, a still texture being a single frame
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<Texture>,
+    fps: f32,
+    mode: LoopMode,
+}
+
+impl Animation {
+    pub fn still(texture: Texture) -> Self {
+        Self {
+            frames: vec![texture],
+            fps: 0.0,
+            mode: LoopMode::Loop,
+        }
+    }
+
+    /// Builds the frames with `load`, which reads a PNG from its path
+    pub fn from_config(config: AnimationConfig, load: impl Fn(&str) -> Texture) -> Self {
+        let mut frames = config
+            .frames
+            .iter()
+            .map(|path| load(path))
+            .collect::<Vec<Texture>>();
+        if let Some(SpriteSheetConfig {
+            path,
+            columns,
+            rows,
+            count,
+        }) = config.sheet
+        {
+            let sheet = load(&path);
+            let (columns, rows) = (columns.max(1), rows.max(1));
+            let (width, height) = (sheet.width / columns, sheet.height / rows);
+            frames.extend(
+                (0..count.unwrap_or(columns * rows).min(columns * rows))
+                    .map(|i| sheet.crop(i % columns * width, i / columns * height, width, height)),
+            );
+        }
+        if frames.is_empty() {
+            frames.push(Texture::default());
+        }
+        Self {
+            frames,
+            fps: config.fps.max(0.0),
+            mode: config.mode,
+        }
+    }
+
+    /// Frame shown `time` seconds after the animation started
+    pub fn frame(&self, time: f32) -> &Texture {
+        let count = self.frames.len();
+        let step = (time.max(0.0) * self.fps) as usize;
+        let index = match self.mode {
+            LoopMode::Loop => step % count,
+            LoopMode::Once => step.min(count - 1),
+            LoopMode::PingPong if count > 1 => {
+                let step = step % (2 * count - 2);
+                if step < count {
+                    step
+                } else {
+                    2 * count - 2 - step
+                }
+            }
+            LoopMode::PingPong => 0,
+        };
+        &self.frames[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn animation(mode: LoopMode) -> Animation {
+        Animation::from_config(
+            AnimationConfig {
+                frames: vec!["red".into(), "green".into(), "blue".into()],
+                sheet: None,
+                fps: 2.0,
+                mode,
+            },
+            |path| Texture::new(vec![Color::from_name(path).unwrap()], 1, 1),
+        )
+    }
+
+    #[test]
+    fn test_frame() {
+        let frames = |mode| {
+            let animation = animation(mode);
+            (0..6)
+                .map(|i| animation.frame(i as f32 * 0.5).pixels[0])
+                .collect::<Vec<Color>>()
+        };
+        let (r, g, b) = (Color::RED, Color::GREEN, Color::BLUE);
+        assert_eq!(frames(LoopMode::Loop), vec![r, g, b, r, g, b]);
+        assert_eq!(frames(LoopMode::Once), vec![r, g, b, b, b, b]);
+        assert_eq!(frames(LoopMode::PingPong), vec![r, g, b, g, r, g]);
+    }
+
+    #[test]
+    fn test_sprite_sheet() {
+        let animation = Animation::from_config(
+            AnimationConfig {
+                frames: Vec::new(),
+                sheet: Some(SpriteSheetConfig {
+                    path: "sheet.png".into(),
+                    columns: 2,
+                    rows: 2,
+                    count: Some(3),
+                }),
+                fps: 1.0,
+                mode: LoopMode::Loop,
+            },
+            |_| {
+                Texture::new(
+                    vec![Color::RED, Color::GREEN, Color::BLUE, Color::BLACK],
+                    2,
+                    2,
+                )
+            },
+        );
+        let frames = animation
+            .frames
+            .iter()
+            .map(|frame| frame.pixels.clone())
+            .collect::<Vec<Vec<Color>>>();
+        assert_eq!(
+            frames,
+            vec![vec![Color::RED], vec![Color::GREEN], vec![Color::BLUE]]
+        );
+    }
+}
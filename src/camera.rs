@@ -17,17 +17,24 @@ const CLIP_DEGREES: fn(f32) -> f32 = |d| {
 
 pub struct Camera {
     pub pos: Vector2,
+    /// Height of the eye in world units, the ground being at 0
+    pub eye_height: f32,
     angle_deg: f32,
+    pitch_deg: f32,
+    projection_distance: f32,
     initial_rays: Vec<Ray>,
     rotated_rays: Vec<Ray>,
 }
 
 impl Camera {
     pub fn new(pos: Vector2, angle: f32, fov: i32, width: u32) -> Self {
-        let rays = Self::init_rays(width as u32, fov);
+        let rays = Self::init_rays(width, fov);
         let mut camera = Self {
             pos,
+            eye_height: 0.0,
             angle_deg: angle,
+            pitch_deg: 0.0,
+            projection_distance: Self::projection_distance_for(width, fov),
             initial_rays: rays.clone(),
             rotated_rays: rays,
         };
@@ -46,6 +53,17 @@ impl Camera {
         self.update_rays();
     }
 
+    /// Looks up (positive angle) or down, never beyond `max_pitch` degrees
+    pub fn tilt(&mut self, angle: f32, max_pitch: f32) {
+        self.pitch_deg = (self.pitch_deg + angle).clamp(-max_pitch, max_pitch);
+    }
+
+    /// Vertical shift in pixels of the horizon from the middle of the screen,
+    /// pitch being emulated by shearing the view rather than rotating it
+    pub fn horizon_offset(&self) -> f32 {
+        self.pitch_deg.to_radians().tan() * self.projection_distance
+    }
+
     fn update_rays(&mut self) {
         self.rotated_rays = self
             .initial_rays
@@ -74,24 +92,100 @@ impl Camera {
         self.left().multiply(-1.0)
     }
 
-    pub fn compute_frame<'a>(&self, map: &'a Map) -> Vec<Option<Hit<'a>>> {
+    /// Distance in pixels between the eye and the projection plane
+    pub fn projection_distance(&self) -> f32 {
+        self.projection_distance
+    }
+
+    /// Screen column and perpendicular distance of a point, if it is in front of the camera
+    pub fn project(&self, point: &Vector2) -> Option<(f32, f32)> {
+        let relative = point.subtract(&self.pos);
+        let depth = relative.dot(&self.forward());
+        if depth <= f32::EPSILON {
+            return None;
+        }
+        // Inverse of the ray spacing of `init_rays`
+        let width = self.rotated_rays.len() as f32;
+        let half_plane = width / 2.0 / self.projection_distance;
+        let plane_x = relative.dot(&self.left()) / depth / half_plane;
+        let last_column = (width - 1.0).max(1.0);
+        Some(((1.0 - plane_x) * last_column / 2.0, depth))
+    }
+
+    pub fn rays(&self) -> &[Ray] {
+        &self.rotated_rays
+    }
+
+    pub fn compute_frame<'a>(&self, map: &'a Map) -> Vec<Vec<Hit<'a>>> {
         self.rotated_rays
             .par_iter()
-            .map(|ray| map.cast(&ray))
+            .map(|ray| map.cast_all(ray))
             .collect()
     }
 
+    fn projection_distance_for(screen_width: u32, fov: i32) -> f32 {
+        (screen_width as f32 / 2.0) / (fov as f32 / 2.0).to_radians().tan()
+    }
+
+    // Rays go through evenly spaced points of a flat projection plane one unit
+    // in front of the eye, from the leftmost column to the rightmost one
     fn init_rays(screen_width: u32, fov: i32) -> Vec<Ray> {
-        let angle_step = fov as f32 / (screen_width - 1) as f32;
-        let half_fov = fov as f32 / 2.0;
+        let half_plane = (fov as f32 / 2.0).to_radians().tan();
+        let last_column = screen_width.saturating_sub(1).max(1) as f32;
         (0..screen_width)
             .map(|i| {
-                let angle = CLIP_DEGREES(half_fov - i as f32 * angle_step).to_radians();
+                let plane_x = 1.0 - 2.0 * i as f32 / last_column;
                 Ray::new(
                     Vector2::new(0.0, 0.0),
-                    Vector2::new(angle.cos(), angle.sin()),
+                    Vector2::new(1.0, plane_x * half_plane),
                 )
             })
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_rays() {
+        let rays = Camera::init_rays(5, 90);
+        assert_eq!(rays.len(), 5);
+        assert_eq!(rays[0].direction, Vector2::new(1.0, 1.0));
+        assert_eq!(rays[2].direction, Vector2::new(1.0, 0.0));
+        assert_eq!(rays[4].direction, Vector2::new(1.0, -1.0));
+    }
+
+    #[test]
+    fn test_project() {
+        let camera = Camera::new(Vector2::new(1.0, 1.0), 90.0, 90, 5);
+        let assert_projects = |point: Vector2, column: f32, depth: f32| {
+            let (x, d) = camera.project(&point).unwrap();
+            assert!((x - column).abs() < 1e-5, "{x} != {column}");
+            assert!((d - depth).abs() < 1e-5, "{d} != {depth}");
+        };
+        assert_projects(Vector2::new(1.0, 3.0), 2.0, 2.0);
+        assert_projects(Vector2::new(3.0, 3.0), 4.0, 2.0);
+        assert_projects(Vector2::new(-1.0, 3.0), 0.0, 2.0);
+        assert_eq!(camera.project(&Vector2::new(1.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_tilt() {
+        let mut camera = Camera::new(Vector2::new(0.0, 0.0), 0.0, 90, 640);
+        assert_eq!(camera.horizon_offset(), 0.0);
+        camera.tilt(45.0, 30.0);
+        camera.tilt(10.0, 30.0);
+        assert_eq!(camera.pitch_deg, 30.0);
+        camera.tilt(-75.0, 30.0);
+        assert_eq!(camera.pitch_deg, -30.0);
+        camera.tilt(75.0, 45.0);
+        assert!((camera.horizon_offset() - 320.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_projection_distance() {
+        assert_eq!(Camera::projection_distance_for(640, 90), 320.0);
+    }
+}
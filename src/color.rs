@@ -0,0 +1,230 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    InvalidHex(String),
+    UnknownName(String),
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidHex(s) => {
+                write!(
+                    f,
+                    "invalid hex color `{s}`, expected `#rrggbb` or `#rrggbbaa`"
+                )
+            }
+            Self::UnknownName(s) => write!(f, "unknown color name `{s}`"),
+        }
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl Color {
+    pub const LIGHTGRAY: Color = Color::new(200, 200, 200, 255);
+    pub const GRAY: Color = Color::new(130, 130, 130, 255);
+    pub const DARKGRAY: Color = Color::new(80, 80, 80, 255);
+    pub const YELLOW: Color = Color::new(253, 249, 0, 255);
+    pub const GOLD: Color = Color::new(255, 203, 0, 255);
+    pub const ORANGE: Color = Color::new(255, 161, 0, 255);
+    pub const PINK: Color = Color::new(255, 109, 194, 255);
+    pub const RED: Color = Color::new(230, 41, 55, 255);
+    pub const MAROON: Color = Color::new(190, 33, 55, 255);
+    pub const GREEN: Color = Color::new(0, 228, 48, 255);
+    pub const LIME: Color = Color::new(0, 158, 47, 255);
+    pub const DARKGREEN: Color = Color::new(0, 117, 44, 255);
+    pub const SKYBLUE: Color = Color::new(102, 191, 255, 255);
+    pub const BLUE: Color = Color::new(0, 121, 241, 255);
+    pub const DARKBLUE: Color = Color::new(0, 82, 172, 255);
+    pub const PURPLE: Color = Color::new(200, 122, 255, 255);
+    pub const VIOLET: Color = Color::new(135, 60, 190, 255);
+    pub const DARKPURPLE: Color = Color::new(112, 31, 126, 255);
+    pub const BEIGE: Color = Color::new(211, 176, 131, 255);
+    pub const BROWN: Color = Color::new(127, 106, 79, 255);
+    pub const DARKBROWN: Color = Color::new(76, 63, 47, 255);
+    pub const WHITE: Color = Color::new(255, 255, 255, 255);
+    pub const BLACK: Color = Color::new(0, 0, 0, 255);
+    pub const BLANK: Color = Color::new(0, 0, 0, 0);
+    pub const MAGENTA: Color = Color::new(255, 0, 255, 255);
+    pub const RAYWHITE: Color = Color::new(245, 245, 245, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn to_rgba(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let color = match name.to_ascii_lowercase().as_str() {
+            "lightgray" => Self::LIGHTGRAY,
+            "gray" => Self::GRAY,
+            "darkgray" => Self::DARKGRAY,
+            "yellow" => Self::YELLOW,
+            "gold" => Self::GOLD,
+            "orange" => Self::ORANGE,
+            "pink" => Self::PINK,
+            "red" => Self::RED,
+            "maroon" => Self::MAROON,
+            "green" => Self::GREEN,
+            "lime" => Self::LIME,
+            "darkgreen" => Self::DARKGREEN,
+            "skyblue" => Self::SKYBLUE,
+            "blue" => Self::BLUE,
+            "darkblue" => Self::DARKBLUE,
+            "purple" => Self::PURPLE,
+            "violet" => Self::VIOLET,
+            "darkpurple" => Self::DARKPURPLE,
+            "beige" => Self::BEIGE,
+            "brown" => Self::BROWN,
+            "darkbrown" => Self::DARKBROWN,
+            "white" => Self::WHITE,
+            "black" => Self::BLACK,
+            "blank" => Self::BLANK,
+            "magenta" => Self::MAGENTA,
+            "raywhite" => Self::RAYWHITE,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        match hex.len() {
+            6 => Some(Self::new(channel(0)?, channel(2)?, channel(4)?, 255)),
+            8 => Some(Self::new(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Draws `over` on top of this color according to its alpha
+    pub fn blend(&self, over: &Self) -> Self {
+        Color {
+            a: self.a.max(over.a),
+            ..self.lerp(over, over.a as f32 / 255.0)
+        }
+    }
+
+    /// Multiplies the red, green and blue channels by the given factors
+    pub fn scale(&self, [r, g, b]: [f32; 3]) -> Self {
+        let scale = |c: u8, f: f32| (c as f32 * f).round().clamp(0.0, 255.0) as u8;
+        Self::new(scale(self.r, r), scale(self.g, g), scale(self.b, b), self.a)
+    }
+
+    /// Linear interpolation towards `other`, `t` being clamped between 0 and 1
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('#') {
+            Some(hex) => Self::from_hex(hex).ok_or_else(|| ParseColorError::InvalidHex(s.into())),
+            None => Self::from_name(s).ok_or_else(|| ParseColorError::UnknownName(s.into())),
+        }
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ParseColorError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("#ff8000".parse(), Ok(Color::new(255, 128, 0, 255)));
+        assert_eq!("#FF800040".parse(), Ok(Color::new(255, 128, 0, 64)));
+        assert_eq!("SkyBlue".parse(), Ok(Color::SKYBLUE));
+        assert_eq!(
+            "#ff80".parse::<Color>(),
+            Err(ParseColorError::InvalidHex("#ff80".into()))
+        );
+        assert_eq!(
+            "#gg8000".parse::<Color>(),
+            Err(ParseColorError::InvalidHex("#gg8000".into()))
+        );
+        assert_eq!(
+            "fog".parse::<Color>(),
+            Err(ParseColorError::UnknownName("fog".into()))
+        );
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let error = serde_json::from_str::<Color>("\"#12\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid hex color `#12`, expected `#rrggbb` or `#rrggbbaa`"
+        );
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(Color::BLACK.lerp(&Color::WHITE, 0.0), Color::BLACK);
+        assert_eq!(
+            Color::BLACK.lerp(&Color::WHITE, 0.5),
+            Color::new(128, 128, 128, 255)
+        );
+        assert_eq!(Color::BLACK.lerp(&Color::WHITE, 2.0), Color::WHITE);
+    }
+
+    #[test]
+    fn test_scale() {
+        assert_eq!(
+            Color::new(100, 100, 100, 128).scale([0.5, 1.0, 3.0]),
+            Color::new(50, 100, 255, 128)
+        );
+    }
+
+    #[test]
+    fn test_blend() {
+        assert_eq!(Color::BLACK.blend(&Color::WHITE), Color::WHITE);
+        assert_eq!(Color::BLACK.blend(&Color::BLANK), Color::BLACK);
+        assert_eq!(
+            Color::BLACK.blend(&Color::new(255, 255, 255, 128)),
+            Color::new(128, 128, 128, 255)
+        );
+    }
+}
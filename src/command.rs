@@ -0,0 +1,19 @@
+/// What the player asks the scene to do, whatever the input device
+pub enum Command {
+    Move(Direction, f32),
+    Look(f32),
+    Pitch(f32),
+    Jump,
+    Crouch,
+    /// Moves a light of the scene to the player's eye
+    PlaceLight(usize),
+    /// Opens or closes the door in front of the player
+    Use,
+}
+
+pub enum Direction {
+    Forward,
+    Backward,
+    Left,
+    Right,
+}
@@ -2,7 +2,10 @@ use std::{collections::HashMap, path::PathBuf};
 
 use serde::Deserialize;
 
-use crate::math::{geometry::Line, Vector2};
+use crate::{
+    color::Color,
+    math::{geometry::Shape, Vector2},
+};
 
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct Screen {
@@ -18,14 +21,82 @@ pub struct Player {
     pub move_speed: f32,
     #[serde(rename = "collisionRadius")]
     pub collision_radius: f32,
+    /// Highest angle in degrees the player can look up or down
+    #[serde(rename = "maxPitch", default = "default_max_pitch")]
+    pub max_pitch: f32,
+    /// Heights of the eye above the floor when standing and crouching
+    #[serde(rename = "eyeHeight", default = "default_eye_height")]
+    pub eye_height: f32,
+    #[serde(rename = "crouchHeight", default = "default_crouch_height")]
+    pub crouch_height: f32,
+    /// Upward speed given by a jump and downward acceleration, in units per second
+    #[serde(rename = "jumpSpeed", default = "default_jump_speed")]
+    pub jump_speed: f32,
+    #[serde(default = "default_gravity")]
+    pub gravity: f32,
+}
+
+fn default_max_pitch() -> f32 {
+    30.0
+}
+
+fn default_eye_height() -> f32 {
+    0.5
+}
+
+fn default_crouch_height() -> f32 {
+    0.25
+}
+
+fn default_jump_speed() -> f32 {
+    3.0
+}
+
+fn default_gravity() -> f32 {
+    10.0
+}
+
+/// How textures are sampled between their pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureFilter {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+fn default_mipmaps() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// Horizontal field of view in degrees
     pub fov: i32,
     pub player: Player,
     pub screen: Screen,
     pub entry: PathBuf,
+    #[serde(default)]
+    pub filter: TextureFilter,
+    /// Whether distant walls and floors use smaller copies of their textures
+    #[serde(default = "default_mipmaps")]
+    pub mipmaps: bool,
+    /// Number of mirrors a ray can bounce off, mirrors being drawn like
+    /// regular walls past it
+    #[serde(rename = "maxReflections", default = "default_max_reflections")]
+    pub max_reflections: u32,
+    /// Number of portals a ray can go through, portals being drawn like
+    /// regular walls past it
+    #[serde(rename = "maxPortals", default = "default_max_portals")]
+    pub max_portals: u32,
+}
+
+fn default_max_reflections() -> u32 {
+    4
+}
+
+fn default_max_portals() -> u32 {
+    8
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -34,27 +105,431 @@ pub struct CameraConfig {
     pub angle: f32,
 }
 
+fn default_wall_top() -> f32 {
+    1.0
+}
+
+/// How the alpha channel of a wall's texture is used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlendMode {
+    /// Alpha is ignored
+    #[default]
+    Opaque,
+    /// Mostly transparent pixels are not drawn, like for grates and fences
+    AlphaTest,
+    /// Pixels are blended over what lies behind them, like for stained glass
+    AlphaBlend,
+}
+
+/// How a texture is laid on a wall, repeating every `scale` world units
+/// from the start of the wall's line and from its top
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct WallMappingConfig {
+    #[serde(default = "default_one")]
+    pub scale: f32,
+    /// Shift of the texture along the wall and downwards, in world units
+    #[serde(default)]
+    pub offset: Vector2,
+    #[serde(rename = "flipHorizontal", default)]
+    pub flip_horizontal: bool,
+    #[serde(rename = "flipVertical", default)]
+    pub flip_vertical: bool,
+    /// Turns the texture a quarter clockwise
+    #[serde(default)]
+    pub rotate: bool,
+    /// Color the texture is multiplied by
+    #[serde(default = "default_tint")]
+    pub tint: Color,
+}
+
+fn default_tint() -> Color {
+    Color::WHITE
+}
+
+impl Default for WallMappingConfig {
+    fn default() -> Self {
+        Self {
+            scale: default_one(),
+            offset: Vector2::default(),
+            flip_horizontal: false,
+            flip_vertical: false,
+            rotate: false,
+            tint: default_tint(),
+        }
+    }
+}
+
+/// Reflective wall, what it reflects being multiplied by `tint` and losing
+/// `attenuation` of its brightness
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MirrorConfig {
+    #[serde(default = "default_tint")]
+    pub tint: Color,
+    #[serde(default)]
+    pub attenuation: f32,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct WallConfig {
     pub texture: String,
-    pub line: Line,
+    /// Either a `line`, a `circle`, an `arc`, a `bezier` curve or a `spline`
+    #[serde(flatten)]
+    pub shape: Shape,
+    #[serde(flatten)]
+    pub mapping: WallMappingConfig,
+    #[serde(default)]
+    pub blend: BlendMode,
+    /// Heights of the bottom and the top of the wall, the floor being at 0
+    #[serde(default)]
+    pub bottom: f32,
+    #[serde(default = "default_wall_top")]
+    pub top: f32,
+    pub mirror: Option<MirrorConfig>,
+    /// Name other walls can refer to
+    pub id: Option<String>,
+    /// Id of the wall what goes into this one comes out of
+    pub portal: Option<String>,
+}
+
+#[cfg(test)]
+impl WallConfig {
+    /// Opaque wall from the floor up to a height of 1 along a line
+    pub fn line(start: Vector2, end: Vector2) -> Self {
+        Self {
+            texture: String::new(),
+            shape: Shape::Line(crate::math::geometry::Line { start, end }),
+            mapping: WallMappingConfig::default(),
+            blend: BlendMode::Opaque,
+            bottom: 0.0,
+            top: default_wall_top(),
+            mirror: None,
+            id: None,
+            portal: None,
+        }
+    }
+}
+
+/// Closed room whose edges become walls, edges shared with another sector
+/// being openings between the two
+#[derive(Debug, Clone, Deserialize)]
+pub struct SectorConfig {
+    pub points: Vec<Vector2>,
+    pub texture: String,
+    #[serde(rename = "floorHeight", default)]
+    pub floor_height: f32,
+    #[serde(rename = "ceilingHeight", default = "default_wall_top")]
+    pub ceiling_height: f32,
+    #[serde(default)]
+    pub floor: Option<SurfaceConfig>,
+    #[serde(default)]
+    pub ceiling: Option<SurfaceConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MapConfig {
+    #[serde(default)]
     pub walls: Vec<WallConfig>,
+    #[serde(default)]
+    pub sectors: Vec<SectorConfig>,
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+/// Texture applied to a horizontal area, `scale` being the size in world
+/// units covered by one repetition of the texture
+#[derive(Debug, Clone, Deserialize)]
+pub struct SurfaceConfig {
+    pub texture: String,
+    #[serde(default = "default_one")]
+    pub scale: f32,
+    #[serde(default)]
+    pub offset: Vector2,
+    #[serde(default)]
+    pub regions: Vec<SurfaceRegionConfig>,
+}
+
+/// Overrides the surface texture inside an axis-aligned rectangle
+#[derive(Debug, Clone, Deserialize)]
+pub struct SurfaceRegionConfig {
+    pub min: Vector2,
+    pub max: Vector2,
+    pub texture: String,
+    #[serde(default = "default_one")]
+    pub scale: f32,
+    #[serde(default)]
+    pub offset: Vector2,
+}
+
+/// What a sprite's position is relative to vertically
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpriteAnchor {
+    /// Standing on the floor
+    #[default]
+    Floor,
+    /// Floating halfway between the floor and the ceiling
+    Center,
+    /// Hanging from the ceiling
+    Ceiling,
+}
+
+/// Camera-facing picture, `scale` being its height in world units
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpriteConfig {
+    pub position: Vector2,
+    pub texture: String,
+    #[serde(default = "default_one")]
+    pub scale: f32,
+    #[serde(default)]
+    pub anchor: SpriteAnchor,
+}
+
+/// Panoramic texture wrapping 360 degrees around the camera
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkyConfig {
+    pub texture: String,
+    #[serde(default)]
+    pub layers: Vec<SkyLayerConfig>,
+}
+
+/// Panorama drawn over the sky, scrolling `parallax` times as fast as the
+/// camera turns and covering `height` of the sky from the horizon
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkyLayerConfig {
+    pub texture: String,
+    #[serde(default = "default_one")]
+    pub parallax: f32,
+    #[serde(default = "default_one")]
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FogMode {
+    #[default]
+    Linear,
+    #[serde(rename = "exp", alias = "exponential")]
+    Exponential,
+    #[serde(rename = "exp2")]
+    ExponentialSquared,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct FogConfig {
+    /// Distance at which linear fog is opaque, or falloff scale of the
+    /// exponential modes
     pub distance: f32,
-    pub color: String,
+    /// Hex (`#rrggbb`, `#rrggbbaa`) or named color
+    pub color: Color,
+    #[serde(default)]
+    pub mode: FogMode,
+}
+
+fn default_light_height() -> f32 {
+    0.5
+}
+
+/// Point light shining up to `radius` world units away
+#[derive(Debug, Clone, Deserialize)]
+pub struct LightConfig {
+    pub position: Vector2,
+    #[serde(default = "default_light_height")]
+    pub height: f32,
+    #[serde(default = "default_tint")]
+    pub color: Color,
+    #[serde(default = "default_one")]
+    pub intensity: f32,
+    pub radius: f32,
+    /// Whether walls between a lit point and the light block it
+    #[serde(default)]
+    pub shadows: bool,
+    /// Baked into the lightmap instead of being computed every frame
+    #[serde(rename = "static", default)]
+    pub is_static: bool,
+}
+
+fn default_lightmap_resolution() -> f32 {
+    8.0
+}
+
+/// Where the baked lighting of the static lights is stored, `resolution`
+/// being the number of texels per world unit
+#[derive(Debug, Clone, Deserialize)]
+pub struct LightmapConfig {
+    pub path: String,
+    #[serde(default = "default_lightmap_resolution")]
+    pub resolution: f32,
+    /// Whether light reflected once by the walls is baked as well
+    #[serde(default)]
+    pub bounce: bool,
+}
+
+/// Either the path of a PNG, a rectangle of an atlas or an animation
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TextureConfig {
+    Image(String),
+    Region(AtlasRegionConfig),
+    Animation(AnimationConfig),
+}
+
+/// Part of a PNG holding many textures, `rect` being its left, top, width
+/// and height in pixels
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtlasRegionConfig {
+    pub atlas: String,
+    pub rect: [u32; 4],
+}
+
+/// What happens once the last frame of an animation is shown, or the last
+/// keyframe of a path reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoopMode {
+    /// Starts over from the first frame
+    #[default]
+    Loop,
+    /// Stays on the last frame
+    Once,
+    /// Plays backwards down to the first frame, then forwards again
+    PingPong,
+}
+
+/// Grid of frames in a single PNG, read row by row, `count` allowing the
+/// last row not to be full
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpriteSheetConfig {
+    pub path: String,
+    pub columns: u32,
+    pub rows: u32,
+    pub count: Option<u32>,
+}
+
+/// Frames shown `fps` times per second, taken from a list of PNGs or from a sprite sheet
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationConfig {
+    #[serde(default)]
+    pub frames: Vec<String>,
+    pub sheet: Option<SpriteSheetConfig>,
+    pub fps: f32,
+    #[serde(default, rename = "loop")]
+    pub mode: LoopMode,
+}
+
+/// Light level of the walls without any light, walls facing `direction` (in
+/// degrees) being darker by `contrast` than the ones perpendicular to it
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct ShadingConfig {
+    pub ambient: Option<f32>,
+    #[serde(default)]
+    pub contrast: f32,
+    #[serde(default)]
+    pub direction: f32,
+}
+
+/// How a door moves out of the way
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DoorMotionConfig {
+    /// Slides towards the start of its wall by `distance` world units, the
+    /// length of the wall by default
+    Slide { distance: Option<f32> },
+    /// Swings counterclockwise by `angle` degrees around the `hinge`, the
+    /// start of the wall by default
+    Swing { hinge: Option<Vector2>, angle: f32 },
+}
+
+// Doors take a second to open or close fully
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_blocking() -> bool {
+    true
+}
+
+/// Wall of the map opening and closing when the player activates it
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoorConfig {
+    /// Id of the wall
+    pub wall: String,
+    #[serde(flatten)]
+    pub motion: DoorMotionConfig,
+    /// Fraction of the motion covered per second
+    #[serde(rename = "openSpeed", default = "default_speed")]
+    pub open_speed: f32,
+    #[serde(rename = "closeSpeed", default = "default_speed")]
+    pub close_speed: f32,
+    /// Seconds spent open before closing, the door staying open if absent
+    #[serde(rename = "autoClose")]
+    pub auto_close: Option<f32>,
+    /// Whether the door stops when the player is in its way, opening back if
+    /// it was closing, rather than pushing the player aside
+    #[serde(default = "default_blocking")]
+    pub blocking: bool,
+}
+
+/// Speed of a move between two keyframes over time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly
+    EaseIn,
+    /// Ends slowly
+    EaseOut,
+    EaseInOut,
+}
+
+/// Placement of a group of walls `time` seconds into its path, relative to
+/// where the walls are in the map
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct KeyframeConfig {
+    pub time: f32,
+    #[serde(default)]
+    pub offset: Vector2,
+    /// Counterclockwise rotation around the group's pivot, in degrees
+    #[serde(default)]
+    pub angle: f32,
+    /// How the walls move from this keyframe to the next one
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+/// Walls of the map moving together along a path of keyframes
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoverConfig {
+    /// Ids of the walls
+    pub walls: Vec<String>,
+    /// Center of the rotations, the middle of the walls by default
+    pub pivot: Option<Vector2>,
+    pub keyframes: Vec<KeyframeConfig>,
+    #[serde(default, rename = "loop")]
+    pub mode: LoopMode,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct SceneConfig {
     pub camera: CameraConfig,
-    pub textures: HashMap<String, String>,
+    pub textures: HashMap<String, TextureConfig>,
     pub map: MapConfig,
     pub fog: Option<FogConfig>,
+    pub floor: Option<SurfaceConfig>,
+    pub ceiling: Option<SurfaceConfig>,
+    pub sky: Option<SkyConfig>,
+    #[serde(default)]
+    pub sprites: Vec<SpriteConfig>,
+    #[serde(default)]
+    pub lights: Vec<LightConfig>,
+    pub lightmap: Option<LightmapConfig>,
+    #[serde(default)]
+    pub shading: ShadingConfig,
+    #[serde(default)]
+    pub doors: Vec<DoorConfig>,
+    #[serde(default)]
+    pub movers: Vec<MoverConfig>,
 }
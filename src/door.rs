@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use log::error;
+
+use crate::{
+    config::{DoorConfig, DoorMotionConfig},
+    map::{Map, Wall},
+    math::{
+        geometry::{Castable, Transform, Transformed},
+        Vector2,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Motion {
+    // Translation once fully open
+    Slide(Vector2),
+    // Rotation in radians once fully open
+    Swing { hinge: Vector2, angle: f32 },
+}
+
+/// Wall of the map moving out of the way when activated
+#[derive(Debug, Clone)]
+pub struct Door {
+    /// Index of the wall in the map
+    pub wall: usize,
+    // Shape of the wall when closed
+    shape: Arc<dyn Castable>,
+    motion: Motion,
+    open_speed: f32,
+    close_speed: f32,
+    auto_close: Option<f32>,
+    pub blocking: bool,
+    pub state: DoorState,
+    // From 0 when closed to 1 when open
+    openness: f32,
+    // Seconds since the door finished opening
+    open_for: f32,
+}
+
+impl Door {
+    /// Door moving the wall named in `config`, unless it is one of the `moved`
+    /// walls already following another door or mover
+    pub fn from_config(config: DoorConfig, map: &Map, moved: &[usize]) -> Option<Self> {
+        let Some(index) = map.wall_index(&config.wall) else {
+            error!("Door on unknown wall: {}", config.wall);
+            return None;
+        };
+        if moved.contains(&index) {
+            error!("Door on a wall already moved: {}", config.wall);
+            return None;
+        }
+        let wall = &map.walls[index];
+        let motion = match config.motion {
+            DoorMotionConfig::Slide { distance } => {
+                let axis = wall.shape.point(0.0).subtract(&wall.shape.point(1.0));
+                let length = axis.magnitude();
+                if length < f32::EPSILON {
+                    error!("Door on a closed wall cannot slide: {}", config.wall);
+                    return None;
+                }
+                Motion::Slide(axis.multiply(distance.unwrap_or(wall.length) / length))
+            }
+            DoorMotionConfig::Swing { hinge, angle } => Motion::Swing {
+                hinge: hinge.unwrap_or(wall.shape.point(0.0)),
+                angle: angle.to_radians(),
+            },
+        };
+        Some(Self {
+            wall: index,
+            shape: wall.shape.clone(),
+            motion,
+            open_speed: config.open_speed,
+            close_speed: config.close_speed,
+            auto_close: config.auto_close,
+            blocking: config.blocking,
+            state: DoorState::Closed,
+            openness: 0.0,
+            open_for: 0.0,
+        })
+    }
+
+    /// Opens the door if it is closed or closing, closes it otherwise
+    pub fn activate(&mut self) {
+        self.state = match self.state {
+            DoorState::Closed | DoorState::Closing => DoorState::Opening,
+            DoorState::Open | DoorState::Opening => DoorState::Closing,
+        };
+    }
+
+    /// Moves the door's wall, `in_the_way` telling whether the wall would
+    /// hit the player, in which case a blocking door stops, opening back if
+    /// it was closing
+    pub fn update(&mut self, delta: f32, map: &mut Map, in_the_way: impl Fn(&Wall) -> bool) {
+        let previous = self.openness;
+        match self.state {
+            DoorState::Closed => {}
+            DoorState::Opening => {
+                self.openness = (self.openness + self.open_speed * delta).min(1.0);
+                if self.openness >= 1.0 {
+                    self.state = DoorState::Open;
+                    self.open_for = 0.0;
+                }
+            }
+            DoorState::Open => {
+                self.open_for += delta;
+                if self.auto_close.is_some_and(|delay| self.open_for >= delay) {
+                    self.state = DoorState::Closing;
+                }
+            }
+            DoorState::Closing => {
+                self.openness = (self.openness - self.close_speed * delta).max(0.0);
+                if self.openness <= 0.0 {
+                    self.state = DoorState::Closed;
+                }
+            }
+        }
+        if self.openness == previous {
+            return;
+        }
+        self.place(map);
+        if self.blocking && in_the_way(&map.walls[self.wall]) {
+            self.openness = previous;
+            self.state = DoorState::Opening;
+            self.place(map);
+        }
+    }
+
+    fn place(&self, map: &mut Map) {
+        let transform = match self.motion {
+            Motion::Slide(translation) => Transform {
+                angle: 0.0,
+                translation: translation.multiply(self.openness),
+            },
+            Motion::Swing { hinge, angle } => {
+                Transform::around(&hinge, angle * self.openness, &Vector2::default())
+            }
+        };
+        map.move_wall(
+            self.wall,
+            Arc::new(Transformed::new(self.shape.clone(), transform)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::WallConfig,
+        math::{assert_close, Ray},
+    };
+
+    fn door(motion: DoorMotionConfig) -> (Door, Map) {
+        let map = Map::from_lines(vec![WallConfig {
+            id: Some("door".into()),
+            ..WallConfig::line(Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0))
+        }]);
+        let config = DoorConfig {
+            wall: "door".into(),
+            motion,
+            open_speed: 2.0,
+            close_speed: 1.0,
+            auto_close: Some(1.0),
+            blocking: true,
+        };
+        (Door::from_config(config, &map, &[]).unwrap(), map)
+    }
+
+    #[test]
+    fn test_slide() {
+        let (mut door, mut map) = door(DoorMotionConfig::Slide {
+            distance: Some(1.5),
+        });
+        door.activate();
+        door.update(0.25, &mut map, |_| false);
+        assert_eq!(door.state, DoorState::Opening);
+        assert_close(map.walls[0].shape.point(1.0), Vector2::new(1.25, 0.0));
+        door.update(0.5, &mut map, |_| false);
+        assert_eq!(door.state, DoorState::Open);
+        assert_close(map.walls[0].shape.point(0.0), Vector2::new(-1.5, 0.0));
+
+        // Closes by itself, opening back while the player is in the way
+        door.update(1.0, &mut map, |_| false);
+        assert_eq!(door.state, DoorState::Closing);
+        door.update(0.5, &mut map, |_| true);
+        assert_eq!(door.state, DoorState::Opening);
+        assert_close(map.walls[0].shape.point(0.0), Vector2::new(-1.5, 0.0));
+        door.update(0.0, &mut map, |_| false);
+        door.update(1.0, &mut map, |_| false);
+        door.update(1.0, &mut map, |_| false);
+        assert_eq!(door.state, DoorState::Closed);
+        assert_close(map.walls[0].shape.point(0.0), Vector2::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_swing() {
+        let (mut door, mut map) = door(DoorMotionConfig::Swing {
+            hinge: None,
+            angle: 90.0,
+        });
+        door.activate();
+        door.update(1.0, &mut map, |_| false);
+        assert_close(map.walls[0].shape.point(0.0), Vector2::new(0.0, 0.0));
+        assert_close(map.walls[0].shape.point(1.0), Vector2::new(0.0, 2.0));
+        // Rays hit the wall where it swung to
+        let ray = Ray::new(Vector2::new(-1.0, 1.0), Vector2::new(1.0, 0.0));
+        assert_eq!(map.cast_all(&ray).len(), 1);
+        let ray = Ray::new(Vector2::new(1.0, 1.0), Vector2::new(0.0, -1.0));
+        assert!(map.cast_all(&ray).is_empty());
+    }
+
+    #[test]
+    fn test_blocked() {
+        // Stays put while opening into the player, then carries on
+        let (mut door, mut map) = door(DoorMotionConfig::Swing {
+            hinge: None,
+            angle: 90.0,
+        });
+        door.activate();
+        door.update(0.25, &mut map, |_| true);
+        assert_eq!(door.state, DoorState::Opening);
+        assert_close(map.walls[0].shape.point(1.0), Vector2::new(2.0, 0.0));
+        door.update(1.0, &mut map, |_| false);
+        assert_eq!(door.state, DoorState::Open);
+    }
+}
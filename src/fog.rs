@@ -0,0 +1,84 @@
+use crate::{
+    color::Color,
+    config::{FogConfig, FogMode},
+};
+
+#[derive(Debug, Clone)]
+pub struct Fog {
+    distance: f32,
+    color: Color,
+    mode: FogMode,
+}
+
+impl Fog {
+    pub fn from_config(
+        FogConfig {
+            distance,
+            color,
+            mode,
+        }: FogConfig,
+    ) -> Self {
+        Self {
+            distance: distance.max(f32::EPSILON),
+            color,
+            mode,
+        }
+    }
+
+    /// Amount of fog between 0 and 1 at the given distance from the eye
+    pub fn density(&self, distance: f32) -> f32 {
+        let d = distance.max(0.0) / self.distance;
+        let density = match self.mode {
+            FogMode::Linear => d,
+            FogMode::Exponential => 1.0 - (-d).exp(),
+            FogMode::ExponentialSquared => 1.0 - (-d * d).exp(),
+        };
+        density.clamp(0.0, 1.0) * self.color.a as f32 / 255.0
+    }
+
+    pub fn apply(&self, color: Color, distance: f32) -> Color {
+        let fogged = color.lerp(&self.color, self.density(distance));
+        Color {
+            a: color.a,
+            ..fogged
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fog(mode: FogMode) -> Fog {
+        Fog::from_config(FogConfig {
+            distance: 10.0,
+            color: Color::WHITE,
+            mode,
+        })
+    }
+
+    #[test]
+    fn test_density() {
+        let linear = fog(FogMode::Linear);
+        assert_eq!(linear.density(0.0), 0.0);
+        assert_eq!(linear.density(5.0), 0.5);
+        assert_eq!(linear.density(20.0), 1.0);
+
+        let exponential = fog(FogMode::Exponential);
+        let exponential_squared = fog(FogMode::ExponentialSquared);
+        assert_eq!(exponential.density(0.0), 0.0);
+        assert!(exponential.density(5.0) > exponential_squared.density(5.0));
+        assert!(exponential.density(20.0) < exponential_squared.density(20.0));
+    }
+
+    #[test]
+    fn test_apply() {
+        let linear = fog(FogMode::Linear);
+        assert_eq!(linear.apply(Color::BLACK, 0.0), Color::BLACK);
+        assert_eq!(linear.apply(Color::BLACK, 10.0), Color::WHITE);
+        assert_eq!(
+            linear.apply(Color::BLANK, 10.0),
+            Color::new(255, 255, 255, 0)
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// An RGBA8 image stored row-major, top-left first
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * BYTES_PER_PIXEL],
+        }
+    }
+
+    pub fn stride(&self) -> usize {
+        self.width as usize * BYTES_PER_PIXEL
+    }
+
+    pub fn save_png(&self, path: &Path) -> image::ImageResult<()> {
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let frame = Framebuffer::new(4, 3);
+        assert_eq!(frame.stride(), 16);
+        assert_eq!(frame.pixels.len(), 48);
+        assert_eq!(frame.pixels.chunks_exact(frame.stride()).count(), 3);
+    }
+}
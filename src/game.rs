@@ -3,47 +3,43 @@ use std::time;
 use raylib::prelude::*;
 
 use crate::{
-    config::{Config, Screen},
+    command::{Command, Direction},
+    config::{Config, SceneConfig},
+    framebuffer::Framebuffer,
     scene::Scene,
 };
 
-const CEILING_COLOR: Color = Color::LIGHTGRAY;
-const FLOOR_COLOR: Color = Color::BROWN;
-
-pub enum Command {
-    Move(Direction, f32),
-    Look(f32),
-}
-
-pub enum Direction {
-    Forward,
-    Backward,
-    Left,
-    Right,
-}
-
 pub struct Game {
     config: Config,
     rl: RaylibHandle,
     thread: RaylibThread,
     scene: Scene,
+    frame: Framebuffer,
+    frame_texture: Texture2D,
 }
 
 impl Game {
-    pub fn new(config: Config) -> Self {
-        let (rl, thread) = raylib::init()
-            .size(config.screen.width as i32, config.screen.height as i32)
+    pub fn new(config: Config, scene_config: SceneConfig) -> Self {
+        let (width, height) = (config.screen.width, config.screen.height);
+        let (mut rl, thread) = raylib::init()
+            .size(width as i32, height as i32)
             .title("Rayna")
             .build();
 
-        let scene_config =
-            serde_json::from_str(include_str!("../assets/scene.json")).expect("read scene");
+        let frame_texture = rl
+            .load_texture_from_image(
+                &thread,
+                &Image::gen_image_color(width as i32, height as i32, Color::BLACK),
+            )
+            .expect("create frame texture");
 
         Self {
             config: config.clone(),
             rl,
             thread,
             scene: Scene::from_config(&config, scene_config),
+            frame: Framebuffer::new(width, height),
+            frame_texture,
         }
     }
 
@@ -51,7 +47,9 @@ impl Game {
         let mut time_keeper = time::Instant::now();
         while !self.rl.window_should_close() {
             let now = time::Instant::now();
-            self.handle_inputs(now.duration_since(time_keeper).as_secs_f32());
+            let delta = now.duration_since(time_keeper).as_secs_f32();
+            self.handle_inputs(delta);
+            self.scene.update(delta);
             self.draw();
             time_keeper = now;
         }
@@ -81,24 +79,42 @@ impl Game {
             inputs.push(Command::Look(-rotation_speed));
         }
 
+        if self.rl.is_key_down(KeyboardKey::KEY_R) || self.rl.is_key_down(KeyboardKey::KEY_UP) {
+            inputs.push(Command::Pitch(rotation_speed));
+        } else if self.rl.is_key_down(KeyboardKey::KEY_F)
+            || self.rl.is_key_down(KeyboardKey::KEY_DOWN)
+        {
+            inputs.push(Command::Pitch(-rotation_speed));
+        }
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            inputs.push(Command::Jump);
+        }
+        if self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || self.rl.is_key_down(KeyboardKey::KEY_C)
+        {
+            inputs.push(Command::Crouch);
+        }
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_L) {
+            inputs.push(Command::PlaceLight(0));
+        }
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_X) {
+            inputs.push(Command::Use);
+        }
+
         self.scene.handle_inputs(&inputs);
     }
 
     fn draw(&mut self) {
-        let Screen { width, height, .. } = self.config.screen;
-        let (width, height) = (width as i32, height as i32);
+        self.scene.render(&mut self.frame);
+        self.frame_texture.update_texture(&self.frame.pixels);
 
         let mut d = self.rl.begin_drawing(&self.thread);
 
-        // Draw the ceiling and the floor
-        d.draw_rectangle(0, 0, width, height / 2, CEILING_COLOR);
-        d.draw_rectangle(0, height / 2, width, height / 2, FLOOR_COLOR);
-
-        // Draw the walls
-        self.scene
-            .get_frame()
-            .into_iter()
-            .for_each(|((x, y), color)| d.draw_pixel(x, y, color));
+        // Draw the frame
+        d.draw_texture(&self.frame_texture, 0, 0, Color::WHITE);
 
         // Draw the crosshair
         // let crosshair_size = 5;
@@ -0,0 +1,24 @@
+//! Raycasting engine behind the `rayna` game, usable to render custom maps
+//! whose walls can be any shape implementing `math::geometry::Castable`,
+//! handed to `scene::Scene::with_walls`
+
+mod animation;
+mod camera;
+pub mod color;
+pub mod command;
+pub mod config;
+mod door;
+mod fog;
+pub mod framebuffer;
+#[cfg(feature = "window")]
+pub mod game;
+mod light;
+mod lightmap;
+pub mod map;
+pub mod math;
+mod mover;
+pub mod scene;
+mod sky;
+mod sprite;
+mod surface;
+mod texture;
@@ -0,0 +1,201 @@
+use crate::{
+    color::Color,
+    config::{BlendMode, LightConfig, ShadingConfig},
+    map::{Hit, Map},
+    math::{Ray, Vector2},
+};
+
+// Hits closer than this to the lit point are the lit wall itself
+const SHADOW_BIAS: f32 = 1e-3;
+
+#[derive(Debug, Clone)]
+pub struct Light {
+    pub position: Vector2,
+    pub height: f32,
+    color: Color,
+    intensity: f32,
+    radius: f32,
+    shadows: bool,
+    /// Baked into the lightmaps of the walls rather than computed live
+    pub is_static: bool,
+}
+
+/// Light every wall receives whatever the lights, depending on the direction it faces
+#[derive(Debug, Clone)]
+pub struct Shading {
+    ambient: f32,
+    contrast: f32,
+    direction: Vector2,
+}
+
+impl Shading {
+    /// The ambient light defaults to full brightness, or to darkness in
+    /// scenes lit by lights
+    pub fn from_config(config: ShadingConfig, has_lights: bool) -> Self {
+        let angle = config.direction.to_radians();
+        Self {
+            ambient: config
+                .ambient
+                .unwrap_or(if has_lights { 0.0 } else { 1.0 })
+                .max(0.0),
+            contrast: config.contrast.clamp(0.0, 1.0),
+            direction: Vector2::new(angle.cos(), angle.sin()),
+        }
+    }
+
+    /// Light level of a wall facing `normal`
+    pub fn factor(&self, normal: &Vector2) -> f32 {
+        let alignment = normal.dot(&self.direction);
+        self.ambient * (1.0 - self.contrast * alignment * alignment)
+    }
+}
+
+/// Opaque walls crossed going from one point to another, nearest first
+pub fn occluders_between<'a>(map: &'a Map, from: &Vector2, to: &Vector2) -> Vec<Hit<'a>> {
+    let direction = to.subtract(from);
+    let length = direction.magnitude();
+    map.cast_all(&Ray::new(*from, direction))
+        .into_iter()
+        .filter(|hit| hit.wall.blend == BlendMode::Opaque)
+        .skip_while(|hit| hit.intersec.distance < SHADOW_BIAS)
+        .take_while(|hit| hit.intersec.distance < length - SHADOW_BIAS)
+        .collect()
+}
+
+/// Whether one of the occluders stops a segment `length` long going from
+/// elevation `from` to elevation `to`, light going through wall openings
+pub fn is_blocked(occluders: &[Hit], length: f32, from: f32, to: f32) -> bool {
+    occluders.iter().any(|hit| {
+        let crossing = from + (to - from) * hit.intersec.distance / length;
+        (hit.wall.bottom..=hit.wall.top).contains(&crossing)
+            && !hit
+                .wall
+                .opening
+                .is_some_and(|opening| (opening.bottom..opening.top).contains(&crossing))
+    })
+}
+
+impl Light {
+    pub fn from_config(config: LightConfig) -> Self {
+        Self {
+            position: config.position,
+            height: config.height,
+            color: config.color,
+            intensity: config.intensity.max(0.0),
+            radius: config.radius.max(f32::EPSILON),
+            shadows: config.shadows,
+            is_static: config.is_static,
+        }
+    }
+
+    /// Light received by a point of a wall facing `normal`, per color channel
+    pub fn illuminance(&self, point: &Vector2, elevation: f32, normal: &Vector2) -> [f32; 3] {
+        let to_light = self.position.subtract(point);
+        let rise = self.height - elevation;
+        let distance = (to_light.dot(&to_light) + rise * rise).sqrt();
+        if distance >= self.radius {
+            return [0.0; 3];
+        }
+        let facing = if distance > f32::EPSILON {
+            (to_light.dot(normal) / distance).max(0.0)
+        } else {
+            1.0
+        };
+        // Falls off smoothly to nothing at the radius
+        let amount = self.intensity * facing * (1.0 - distance / self.radius).powi(2);
+        [self.color.r, self.color.g, self.color.b].map(|c| amount * c as f32 / 255.0)
+    }
+
+    /// Opaque walls between a point and the light, to be tested with
+    /// `is_occluded` at any elevation above that point
+    pub fn occluders<'a>(&self, map: &'a Map, point: &Vector2) -> Vec<Hit<'a>> {
+        if !self.shadows {
+            return Vec::new();
+        }
+        occluders_between(map, point, &self.position)
+    }
+
+    /// Whether one of the occluders of `point` stops the light before it
+    /// reaches `elevation`, light going through the openings of the walls
+    pub fn is_occluded(&self, occluders: &[Hit], point: &Vector2, elevation: f32) -> bool {
+        let length = self.position.subtract(point).magnitude();
+        is_blocked(occluders, length, elevation, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WallConfig;
+
+    fn light(shadows: bool) -> Light {
+        Light::from_config(LightConfig {
+            position: Vector2::new(0.0, 0.0),
+            height: 0.5,
+            color: Color::new(255, 0, 0, 255),
+            intensity: 2.0,
+            radius: 4.0,
+            shadows,
+            is_static: false,
+        })
+    }
+
+    #[test]
+    fn test_illuminance() {
+        let light = light(false);
+        let normal = Vector2::new(-1.0, 0.0);
+        assert_eq!(
+            light.illuminance(&Vector2::new(2.0, 0.0), 0.5, &normal),
+            [0.5, 0.0, 0.0]
+        );
+        // Walls facing away and points out of reach stay dark
+        assert_eq!(
+            light.illuminance(&Vector2::new(2.0, 0.0), 0.5, &normal.multiply(-1.0)),
+            [0.0; 3]
+        );
+        assert_eq!(
+            light.illuminance(&Vector2::new(5.0, 0.0), 0.5, &normal),
+            [0.0; 3]
+        );
+    }
+
+    #[test]
+    fn test_shading() {
+        let shading = Shading::from_config(
+            ShadingConfig {
+                ambient: Some(0.8),
+                contrast: 0.5,
+                direction: 0.0,
+            },
+            true,
+        );
+        assert_eq!(shading.factor(&Vector2::new(-1.0, 0.0)), 0.4);
+        assert_eq!(shading.factor(&Vector2::new(0.0, 1.0)), 0.8);
+
+        assert_eq!(
+            Shading::from_config(ShadingConfig::default(), false).factor(&Vector2::new(1.0, 0.0)),
+            1.0
+        );
+        assert_eq!(
+            Shading::from_config(ShadingConfig::default(), true).factor(&Vector2::new(1.0, 0.0)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_occlusion() {
+        let map = Map::from_lines(vec![WallConfig {
+            top: 0.5,
+            ..WallConfig::line(Vector2::new(1.0, -1.0), Vector2::new(1.0, 1.0))
+        }]);
+        let point = Vector2::new(2.0, 0.0);
+        assert!(light(false).occluders(&map, &point).is_empty());
+
+        let light = light(true);
+        let occluders = light.occluders(&map, &point);
+        assert_eq!(occluders.len(), 1);
+        // The low wall only shadows the bottom of what is behind it
+        assert!(light.is_occluded(&occluders, &point, 0.0));
+        assert!(!light.is_occluded(&occluders, &point, 1.0));
+    }
+}
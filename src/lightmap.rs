@@ -0,0 +1,350 @@
+use std::{fs::File, io, path::Path};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    light::{is_blocked, occluders_between, Light},
+    map::{Map, Wall},
+    math::Vector2,
+};
+
+// Bounced light is gathered from square patches of walls this wide
+const PATCH_SIZE: f32 = 0.5;
+// How far the walls can be from where a lightmap was baked for, in world units
+const FIT_TOLERANCE: f32 = 1e-4;
+
+/// Light received by every wall of a map, in the order of `Map::walls`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lightmap {
+    pub walls: Vec<WallLightmap>,
+}
+
+/// Light received by each side of a wall, stored bottom row first with the
+/// columns going from the start to the end of the wall's line
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WallLightmap {
+    // The wall and texels per world unit the lightmap was baked for, lightmaps
+    // baked before they were stored fitting no wall
+    #[serde(default)]
+    ends: [[f32; 2]; 2],
+    #[serde(default)]
+    length: f32,
+    #[serde(default)]
+    bottom: f32,
+    #[serde(default)]
+    top: f32,
+    #[serde(default)]
+    resolution: f32,
+    width: usize,
+    height: usize,
+    left: Vec<[f32; 3]>,
+    right: Vec<[f32; 3]>,
+}
+
+// Piece of a wall side reflecting the light it receives
+struct Patch {
+    position: Vector2,
+    elevation: f32,
+    normal: Vector2,
+    area: f32,
+    light: [f32; 3],
+}
+
+fn add([r, g, b]: [f32; 3], [lr, lg, lb]: [f32; 3]) -> [f32; 3] {
+    [r + lr, g + lg, b + lb]
+}
+
+impl WallLightmap {
+    // Computes the light of every texel from its position, elevation and normal
+    fn bake(
+        wall: &Wall,
+        resolution: f32,
+        light: impl Fn(&Vector2, f32, &Vector2) -> [f32; 3],
+    ) -> Self {
+        let width = (wall.length * resolution).ceil().max(1.0) as usize;
+        let height = ((wall.top - wall.bottom) * resolution).ceil().max(1.0) as usize;
+        let side = |facing: f32| {
+            (0..width * height)
+                .map(|i| {
+                    let u = ((i % width) as f32 + 0.5) / width as f32;
+                    let v = ((i / width) as f32 + 0.5) / height as f32;
+                    light(
+                        &wall.shape.point(u),
+                        wall.bottom + v * (wall.top - wall.bottom),
+                        &wall.shape.normal(u).multiply(facing),
+                    )
+                })
+                .collect()
+        };
+        Self {
+            ends: [0.0, 1.0].map(|offset| {
+                let point = wall.shape.point(offset);
+                [point.x, point.y]
+            }),
+            length: wall.length,
+            bottom: wall.bottom,
+            top: wall.top,
+            resolution,
+            width,
+            height,
+            left: side(1.0),
+            right: side(-1.0),
+        }
+    }
+
+    /// Bilinearly filtered light of one side of the wall, `u` going along the
+    /// wall's line and `v` from its bottom to its top
+    pub fn sample(&self, left: bool, u: f32, v: f32) -> [f32; 3] {
+        let texels = if left { &self.left } else { &self.right };
+        let x = (u * self.width as f32 - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x.fract(), y.fract());
+        let texel =
+            |x: usize, y: usize, weight: f32| texels[y * self.width + x].map(|c| c * weight);
+        [
+            texel(x0, y0, (1.0 - fx) * (1.0 - fy)),
+            texel(x1, y0, fx * (1.0 - fy)),
+            texel(x0, y1, (1.0 - fx) * fy),
+            texel(x1, y1, fx * fy),
+        ]
+        .into_iter()
+        .fold([0.0; 3], add)
+    }
+
+    /// Whether the lightmap was baked for the wall as it is now, at the
+    /// `resolution`, and its texels agree with its size
+    pub fn fits(&self, wall: &Wall, resolution: f32) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= FIT_TOLERANCE;
+        let texels = self.width * self.height;
+        let ends = [0.0, 1.0].map(|offset| wall.shape.point(offset));
+        texels > 0
+            && self.left.len() == texels
+            && self.right.len() == texels
+            && self
+                .ends
+                .iter()
+                .zip(ends)
+                .all(|(&[x, y], end)| close(x, end.x) && close(y, end.y))
+            && close(self.length, wall.length)
+            && close(self.bottom, wall.bottom)
+            && close(self.top, wall.top)
+            && close(self.resolution, resolution)
+    }
+
+    fn patches(&self, wall: &Wall, albedo: [f32; 3]) -> Vec<Patch> {
+        let columns = (wall.length / PATCH_SIZE).ceil().max(1.0) as usize;
+        let rows = ((wall.top - wall.bottom) / PATCH_SIZE).ceil().max(1.0) as usize;
+        let area = wall.length / columns as f32 * (wall.top - wall.bottom) / rows as f32;
+        (0..columns * rows)
+            .flat_map(|i| {
+                let u = ((i % columns) as f32 + 0.5) / columns as f32;
+                let v = ((i / columns) as f32 + 0.5) / rows as f32;
+                [true, false].map(|left| Patch {
+                    position: wall.shape.point(u),
+                    elevation: wall.bottom + v * (wall.top - wall.bottom),
+                    normal: wall.shape.normal(u).multiply(if left { 1.0 } else { -1.0 }),
+                    area,
+                    light: {
+                        let [r, g, b] = self.sample(left, u, v);
+                        [r * albedo[0], g * albedo[1], b * albedo[2]]
+                    },
+                })
+            })
+            // Nothing reflects light where the wall is open
+            .filter(|patch| {
+                !wall
+                    .opening
+                    .is_some_and(|opening| (opening.bottom..opening.top).contains(&patch.elevation))
+            })
+            .collect()
+    }
+}
+
+impl Lightmap {
+    /// Bakes the direct light of `lights` and, with `bounce`, the light the
+    /// walls reflect once according to their `albedo`
+    pub fn bake(
+        map: &Map,
+        lights: &[Light],
+        albedo: impl Fn(&Wall) -> [f32; 3],
+        resolution: f32,
+        bounce: bool,
+    ) -> Self {
+        let direct = |point: &Vector2, elevation: f32, normal: &Vector2| {
+            lights
+                .iter()
+                .filter(|light| {
+                    let occluders = occluders_between(map, point, &light.position);
+                    let length = light.position.subtract(point).magnitude();
+                    !is_blocked(&occluders, length, elevation, light.height)
+                })
+                .map(|light| light.illuminance(point, elevation, normal))
+                .fold([0.0; 3], add)
+        };
+        let mut walls = map
+            .walls
+            .par_iter()
+            .map(|wall| WallLightmap::bake(wall, resolution, direct))
+            .collect::<Vec<WallLightmap>>();
+
+        if bounce {
+            let patches = map
+                .walls
+                .iter()
+                .zip(&walls)
+                .flat_map(|(wall, lightmap)| lightmap.patches(wall, albedo(wall)))
+                .collect::<Vec<Patch>>();
+            let bounced = |point: &Vector2, elevation: f32, normal: &Vector2| {
+                patches
+                    .iter()
+                    .filter_map(|patch| {
+                        let offset = patch.position.subtract(point);
+                        let rise = patch.elevation - elevation;
+                        let squared = (offset.dot(&offset) + rise * rise).max(patch.area);
+                        let distance = squared.sqrt();
+                        let receiving = offset.dot(normal) / distance;
+                        let emitting = -offset.dot(&patch.normal) / distance;
+                        if receiving <= 0.0 || emitting <= 0.0 {
+                            return None;
+                        }
+                        let occluders = occluders_between(map, point, &patch.position);
+                        if is_blocked(&occluders, offset.magnitude(), elevation, patch.elevation) {
+                            return None;
+                        }
+                        // Form factor of a small diffuse patch
+                        let factor =
+                            receiving * emitting * patch.area / (std::f32::consts::PI * squared);
+                        Some(patch.light.map(|c| c * factor))
+                    })
+                    .fold([0.0; 3], add)
+            };
+            walls = map
+                .walls
+                .par_iter()
+                .zip(walls)
+                .map(|(wall, direct)| {
+                    let bounce = WallLightmap::bake(wall, resolution, bounced);
+                    let sum = |a: Vec<[f32; 3]>, b: Vec<[f32; 3]>| {
+                        a.into_iter().zip(b).map(|(a, b)| add(a, b)).collect()
+                    };
+                    WallLightmap {
+                        left: sum(direct.left, bounce.left),
+                        right: sum(direct.right, bounce.right),
+                        ..direct
+                    }
+                })
+                .collect();
+        }
+        Self { walls }
+    }
+
+    /// Whether there is a lightmap baked at the `resolution` for every wall
+    /// of `map` as it is now
+    pub fn fits(&self, map: &Map, resolution: f32) -> bool {
+        self.walls.len() == map.walls.len()
+            && self
+                .walls
+                .iter()
+                .zip(&map.walls)
+                .all(|(lightmap, wall)| lightmap.fits(wall, resolution))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_reader(io::BufReader::new(File::open(
+            path,
+        )?))?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        Ok(serde_json::to_writer(
+            io::BufWriter::new(File::create(path)?),
+            self,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        config::{LightConfig, WallConfig},
+    };
+
+    fn wall(start: (f32, f32), end: (f32, f32)) -> WallConfig {
+        WallConfig::line(Vector2::new(start.0, start.1), Vector2::new(end.0, end.1))
+    }
+
+    #[test]
+    fn test_bake() {
+        // Two walls facing each other, the light being on the left of the first
+        let map = Map::from_lines(vec![
+            wall((0.0, 0.0), (2.0, 0.0)),
+            wall((2.0, 1.0), (0.0, 1.0)),
+        ]);
+        let light = Light::from_config(LightConfig {
+            position: Vector2::new(1.0, 0.5),
+            height: 0.5,
+            color: Color::WHITE,
+            intensity: 1.0,
+            radius: 4.0,
+            shadows: true,
+            is_static: true,
+        });
+        let direct = Lightmap::bake(&map, std::slice::from_ref(&light), |_| [1.0; 3], 4.0, false);
+        let lit = direct.walls[0].sample(true, 0.5, 0.5);
+        assert!(lit[0] > 0.0);
+        assert_eq!(direct.walls[0].sample(false, 0.5, 0.5), [0.0; 3]);
+
+        let bounced = Lightmap::bake(&map, &[light], |_| [1.0; 3], 4.0, true);
+        assert!(bounced.walls[0].sample(true, 0.5, 0.5)[0] > lit[0]);
+        assert_eq!(bounced.walls[0].sample(false, 0.5, 0.5), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_sample() {
+        let lightmap = WallLightmap {
+            width: 2,
+            height: 1,
+            left: vec![[0.0; 3], [1.0; 3]],
+            right: vec![[1.0; 3], [1.0; 3]],
+            ..Default::default()
+        };
+        assert_eq!(lightmap.sample(true, 0.0, 0.5), [0.0; 3]);
+        assert_eq!(lightmap.sample(true, 0.5, 0.5), [0.5; 3]);
+        assert_eq!(lightmap.sample(true, 1.0, 0.5), [1.0; 3]);
+        assert_eq!(lightmap.sample(false, 0.0, 0.5), [1.0; 3]);
+    }
+
+    #[test]
+    fn test_fits() {
+        let map = Map::from_lines(vec![wall((0.0, 0.0), (2.0, 0.0))]);
+        let lightmap = Lightmap::bake(&map, &[], |_| [1.0; 3], 2.0, false);
+        assert!(lightmap.fits(&map, 2.0));
+        assert!(!lightmap.fits(&map, 4.0));
+
+        // Walls moved, resized or added since the lightmap was baked
+        let fits = |walls| lightmap.fits(&Map::from_lines(walls), 2.0);
+        assert!(!fits(vec![wall((0.0, 1.0), (2.0, 1.0))]));
+        assert!(!fits(vec![wall((0.0, 0.0), (3.0, 0.0))]));
+        assert!(!fits(vec![WallConfig {
+            top: 2.0,
+            ..wall((0.0, 0.0), (2.0, 0.0))
+        }]));
+        assert!(!fits(vec![
+            wall((0.0, 0.0), (2.0, 0.0)),
+            wall((0.0, 1.0), (2.0, 1.0))
+        ]));
+
+        // Texels not agreeing with the size, as in a file edited by hand
+        let mut broken = lightmap.clone();
+        broken.walls[0].width = 0;
+        assert!(!broken.fits(&map, 2.0));
+        let mut broken = lightmap.clone();
+        broken.walls[0].right.pop();
+        assert!(!broken.fits(&map, 2.0));
+    }
+}
@@ -1,22 +1,58 @@
-use game::Game;
+use std::{env, path::Path};
 
-mod camera;
-mod config;
-mod game;
-mod map;
-mod math;
-mod scene;
-mod texture;
+use log::{error, info};
+#[cfg(feature = "window")]
+use rayna::game::Game;
+use rayna::{
+    config::{Config, SceneConfig},
+    framebuffer::Framebuffer,
+    scene::Scene,
+};
 
 /// TODO
 /// - Optim
-/// - Skybox
 /// - Better collision
 /// - Map editor
-/// - Fog
-
 fn main() {
     env_logger::init();
-    let config = serde_json::from_str(include_str!("../assets/config.json")).expect("read config");
-    Game::new(config).run();
+    let config: Config =
+        serde_json::from_str(include_str!("../assets/config.json")).expect("read config");
+    let scene_config: SceneConfig =
+        serde_json::from_str(include_str!("../assets/scene.json")).expect("read scene");
+
+    let args = env::args().collect::<Vec<String>>();
+    match args.get(1).map(String::as_str) {
+        Some("render") => render(
+            &config,
+            scene_config,
+            Path::new(args.get(2).map_or("frame.png", String::as_str)),
+        ),
+        Some("bake") => bake(&config, scene_config),
+        #[cfg(feature = "window")]
+        _ => Game::new(config, scene_config).run(),
+        #[cfg(not(feature = "window"))]
+        _ => error!("Built without the window feature, only render and bake are available"),
+    }
+}
+
+/// Renders a single frame without opening a window and saves it as a PNG
+fn render(config: &Config, scene_config: SceneConfig, output: &Path) {
+    let scene = Scene::from_config(config, scene_config);
+    let mut frame = Framebuffer::new(config.screen.width, config.screen.height);
+    scene.render(&mut frame);
+    frame.save_png(output).expect("save frame");
+    info!("Frame saved to {}", output.display());
+}
+
+/// Bakes the static lights of the scene into the lightmap it points to
+fn bake(config: &Config, scene_config: SceneConfig) {
+    let Some(lightmap_config) = scene_config.lightmap.clone() else {
+        error!("The scene has no lightmap to bake");
+        return;
+    };
+    let scene = Scene::from_config(config, scene_config);
+    let lightmap = scene.bake_lightmap(lightmap_config.resolution, lightmap_config.bounce);
+    let output = Path::new(&lightmap_config.path);
+    lightmap.save(output).expect("save lightmap");
+    info!("Lightmap saved to {}", output.display());
 }
@@ -1,21 +1,91 @@
+use std::sync::Arc;
 
 use crate::{
-    config::WallConfig,
-    math::{geometry::Line, Intersection, Ray},
+    color::Color,
+    config::{BlendMode, MapConfig, MirrorConfig, SectorConfig, WallConfig, WallMappingConfig},
+    lightmap::WallLightmap,
+    math::{
+        geometry::{Bounds, Castable, Polygon},
+        Intersection, Ray, Side, Vector2,
+    },
+    surface::Surface,
 };
+use log::error;
 use rayon::prelude::*;
 
+const VERTEX_TOLERANCE: f32 = 1e-4;
+
 #[derive(Debug, Clone)]
 pub struct Map {
     pub walls: Vec<Wall>,
+    pub sectors: Vec<Sector>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sector {
+    pub polygon: Polygon,
+    pub floor_height: f32,
+    pub ceiling_height: f32,
+    pub floor: Option<Surface>,
+    pub ceiling: Option<Surface>,
+}
+
+/// Part of a wall through which the sector behind it is visible and reachable,
+/// `left` and `right` being the sectors on each side of the wall's line
+#[derive(Debug, Clone, Copy)]
+pub struct Opening {
+    pub bottom: f32,
+    pub top: f32,
+    pub left: usize,
+    pub right: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct Wall {
-    pub line: Line,
+    pub id: Option<String>,
+    pub shape: Arc<dyn Castable>,
+    /// Box around the shape, rays missing it not being cast against the shape
+    pub bounds: Bounds,
     pub length: f32,
     pub texture_name: String,
-    pub repeat_texture: i32,
+    pub mapping: WallMapping,
+    pub bottom: f32,
+    pub top: f32,
+    pub opening: Option<Opening>,
+    pub blend: BlendMode,
+    pub lightmap: Option<WallLightmap>,
+    pub mirror: Option<Mirror>,
+    pub portal: Option<Portal>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Mirror {
+    tint: Color,
+    attenuation: f32,
+}
+
+/// Link to another wall, what goes into the wall coming out of the target as
+/// if the start of each wall was glued to the end of the other
+#[derive(Debug, Clone, Copy)]
+pub struct Portal {
+    pub target: usize,
+    from: Vector2,
+    to: Vector2,
+    /// Rotation in radians and change of height from the wall to its target
+    pub angle: f32,
+    pub elevation: f32,
+}
+
+/// Placement of a wall's texture
+#[derive(Debug, Clone, Copy)]
+pub struct WallMapping {
+    /// Size in world units covered by the texture
+    pub scale: f32,
+    offset: Vector2,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    rotate: bool,
+    pub tint: Color,
 }
 
 #[derive(Debug, Clone)]
@@ -24,40 +94,430 @@ pub struct Hit<'a> {
     pub wall: &'a Wall,
 }
 
-impl Map {
-    pub fn from_lines(walls: Vec<WallConfig>) -> Self {
+impl Sector {
+    pub fn from_config(config: SectorConfig) -> Self {
+        let mut polygon = Polygon::new(config.points);
+        // Keep the inside of the sector on the left of its edges
+        if polygon.signed_area() < 0.0 {
+            polygon.points.reverse();
+        }
+        Self {
+            polygon,
+            floor_height: config.floor_height.min(config.ceiling_height),
+            ceiling_height: config.ceiling_height.max(config.floor_height),
+            floor: config.floor.map(Surface::from_config),
+            ceiling: config.ceiling.map(Surface::from_config),
+        }
+    }
+}
+
+impl Opening {
+    /// Sector a ray enters when going through the opening from the `side`
+    /// it hit
+    pub fn entered_sector(&self, side: Side) -> usize {
+        match side {
+            Side::Right => self.left,
+            Side::Left => self.right,
+        }
+    }
+}
+
+impl Portal {
+    fn new(target: usize, wall: &Wall, other: &Wall) -> Self {
+        // Curved walls are linked through the chords between their ends
+        let angle = |v: Vector2| v.y.atan2(v.x);
+        let chord = |shape: &Arc<dyn Castable>| shape.point(1.0).subtract(&shape.point(0.0));
+        Self {
+            target,
+            from: wall.shape.point(0.5),
+            to: other.shape.point(0.5),
+            angle: angle(chord(&other.shape).multiply(-1.0)) - angle(chord(&wall.shape)),
+            elevation: other.bottom - wall.bottom,
+        }
+    }
+
+    /// Where a point around the wall ends up around the target
+    pub fn point(&self, point: &Vector2) -> Vector2 {
+        point.subtract(&self.from).rotate(self.angle).add(&self.to)
+    }
+
+    pub fn direction(&self, direction: &Vector2) -> Vector2 {
+        direction.rotate(self.angle)
+    }
+}
+
+impl Mirror {
+    pub fn from_config(config: MirrorConfig) -> Self {
+        Self {
+            tint: config.tint,
+            attenuation: config.attenuation.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Color of the mirror showing the reflected `color`
+    pub fn reflect(&self, color: Color) -> Color {
+        let brightness = 1.0 - self.attenuation;
+        color.scale([self.tint.r, self.tint.g, self.tint.b].map(|c| brightness * c as f32 / 255.0))
+    }
+}
+
+impl WallMapping {
+    pub fn from_config(config: WallMappingConfig) -> Self {
+        Self {
+            scale: if config.scale.abs() > f32::EPSILON {
+                config.scale.abs()
+            } else {
+                1.0
+            },
+            offset: config.offset,
+            flip_horizontal: config.flip_horizontal,
+            flip_vertical: config.flip_vertical,
+            rotate: config.rotate,
+            tint: config.tint,
+        }
+    }
+
+    /// Texture coordinates between 0 and 1 of the point `along` world units
+    /// from the start of the wall and `below` world units under its top
+    pub fn uv(&self, along: f32, below: f32) -> (f32, f32) {
+        let u = (along + self.offset.x) / self.scale;
+        let v = (below + self.offset.y) / self.scale;
+        let (u, v) = if self.rotate { (v, -u) } else { (u, v) };
+        let (u, v) = (u.rem_euclid(1.0), v.rem_euclid(1.0));
+        (
+            if self.flip_horizontal { 1.0 - u } else { u },
+            if self.flip_vertical { 1.0 - v } else { v },
+        )
+    }
+}
+
+impl Wall {
+    pub fn new(shape: Arc<dyn Castable>, texture_name: String, bottom: f32, top: f32) -> Self {
         Self {
-            walls: walls
-                .into_iter()
-                .map(
-                    |WallConfig {
-                         line,
-                         texture: texture_name,
-                     }| Wall {
-                        line,
-                        length: line.end.subtract(&line.start).magnitude(),
-                        texture_name,
-                        repeat_texture: line.end.subtract(&line.start).magnitude() as i32,
+            id: None,
+            bounds: shape.bounds(),
+            length: shape.length(),
+            shape,
+            texture_name,
+            mapping: WallMapping::from_config(WallMappingConfig::default()),
+            bottom: bottom.min(top),
+            top: top.max(bottom),
+            opening: None,
+            blend: BlendMode::Opaque,
+            lightmap: None,
+            mirror: None,
+            portal: None,
+        }
+    }
+
+    // Moves the wall to a shape of the same length
+    fn set_shape(&mut self, shape: Arc<dyn Castable>) {
+        self.bounds = shape.bounds();
+        self.shape = shape;
+    }
+}
+
+impl Map {
+    pub fn from_config(config: MapConfig) -> Self {
+        let mut map = Self::from_lines(config.walls);
+        let sectors = config
+            .sectors
+            .into_iter()
+            .map(|sector| (sector.texture.clone(), Sector::from_config(sector)))
+            .collect::<Vec<(String, Sector)>>();
+
+        // Every edge becomes a wall, edges shared by two sectors only once
+        let same_point = |a: &Vector2, b: &Vector2| a.subtract(b).magnitude() < VERTEX_TOLERANCE;
+        for (i, (texture_name, sector)) in sectors.iter().enumerate() {
+            for edge in sector.polygon.edges() {
+                let neighbour = sectors.iter().enumerate().find(|(j, (_, other))| {
+                    *j != i
+                        && other.polygon.edges().any(|other_edge| {
+                            same_point(&other_edge.start, &edge.end)
+                                && same_point(&other_edge.end, &edge.start)
+                        })
+                });
+                let wall = match neighbour {
+                    None => Wall::new(
+                        Arc::new(edge),
+                        texture_name.clone(),
+                        sector.floor_height,
+                        sector.ceiling_height,
+                    ),
+                    Some((j, _)) if j < i => continue,
+                    Some((j, (_, other))) => Wall {
+                        opening: Some(Opening {
+                            bottom: sector.floor_height.max(other.floor_height),
+                            top: sector.ceiling_height.min(other.ceiling_height),
+                            left: i,
+                            right: j,
+                        }),
+                        ..Wall::new(
+                            Arc::new(edge),
+                            texture_name.clone(),
+                            sector.floor_height.min(other.floor_height),
+                            sector.ceiling_height.max(other.ceiling_height),
+                        )
                     },
-                )
-                .collect(),
+                };
+                map.walls.push(wall);
+            }
+        }
+
+        map.sectors = sectors.into_iter().map(|(_, sector)| sector).collect();
+        map
+    }
+
+    /// Moves a wall to a shape of the same length, the portals it goes into
+    /// or comes out of following it
+    pub fn move_wall(&mut self, index: usize, shape: Arc<dyn Castable>) {
+        self.walls[index].set_shape(shape);
+        for i in 0..self.walls.len() {
+            let Some(target) = self.walls[i].portal.as_ref().map(|portal| portal.target) else {
+                continue;
+            };
+            if i == index || target == index {
+                self.walls[i].portal =
+                    Some(Portal::new(target, &self.walls[i], &self.walls[target]));
+            }
         }
     }
 
-    pub fn cast(&self, ray: &Ray) -> Option<Hit> {
+    pub fn from_lines(walls: Vec<WallConfig>) -> Self {
+        let portals = walls
+            .iter()
+            .map(|wall| wall.portal.clone())
+            .collect::<Vec<Option<String>>>();
+        let walls = walls
+            .into_iter()
+            .map(
+                |WallConfig {
+                     shape,
+                     texture,
+                     mapping,
+                     blend,
+                     bottom,
+                     top,
+                     mirror,
+                     id,
+                     ..
+                 }| Wall {
+                    id,
+                    mapping: WallMapping::from_config(mapping),
+                    mirror: mirror.map(Mirror::from_config),
+                    blend,
+                    ..Wall::new(shape.into_castable(), texture, bottom, top)
+                },
+            )
+            .collect::<Vec<Wall>>();
+        let mut map = Self {
+            sectors: Vec::new(),
+            walls,
+        };
+
+        for (i, portal) in portals.iter().enumerate() {
+            let Some(portal) = portal else {
+                continue;
+            };
+            match map.wall_index(portal) {
+                Some(target) => {
+                    map.walls[i].portal =
+                        Some(Portal::new(target, &map.walls[i], &map.walls[target]))
+                }
+                None => error!("Portal to unknown wall: {portal}"),
+            }
+        }
+        map
+    }
+
+    /// Index of the wall with the given id
+    pub fn wall_index(&self, id: &str) -> Option<usize> {
         self.walls
+            .iter()
+            .position(|wall| wall.id.as_deref() == Some(id))
+    }
+
+    /// Every wall crossed by the ray, nearest first
+    pub fn cast_all(&self, ray: &Ray) -> Vec<Hit<'_>> {
+        let mut hits = self
+            .walls
             .par_iter()
+            .filter(|wall| wall.bounds.is_hit_by(ray))
             .filter_map(|wall| {
-                wall.line.cast(&ray).map(|intersection| Hit {
+                wall.shape.cast(ray).map(|intersection| Hit {
                     intersec: intersection,
                     wall,
                 })
             })
-            .min_by(|a, b| {
-                a.intersec
-                    .distance
-                    .partial_cmp(&b.intersec.distance)
-                    .unwrap()
-            })
+            .collect::<Vec<Hit>>();
+        hits.sort_by(|a, b| a.intersec.distance.total_cmp(&b.intersec.distance));
+        hits
+    }
+
+    pub fn sector_at(&self, point: &Vector2) -> Option<&Sector> {
+        self.sectors
+            .iter()
+            .find(|sector| sector.polygon.contains(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{
+        assert_close,
+        geometry::{Transform, Transformed},
+    };
+
+    fn wall(x: f32, top: f32) -> WallConfig {
+        WallConfig {
+            top,
+            ..WallConfig::line(Vector2::new(x, -1.0), Vector2::new(x, 1.0))
+        }
+    }
+
+    #[test]
+    fn test_cast_all() {
+        let map = Map::from_lines(vec![wall(3.0, 2.0), wall(1.0, 0.5), wall(2.0, 1.0)]);
+        let ray = Ray::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0));
+
+        let hits = map.cast_all(&ray);
+        assert_eq!(
+            hits.iter()
+                .map(|hit| hit.intersec.distance)
+                .collect::<Vec<f32>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(hits[0].wall.top, 0.5);
+    }
+
+    fn sector(points: &[(f32, f32)], floor_height: f32, ceiling_height: f32) -> SectorConfig {
+        SectorConfig {
+            points: points.iter().map(|&(x, y)| Vector2::new(x, y)).collect(),
+            texture: String::new(),
+            floor_height,
+            ceiling_height,
+            floor: None,
+            ceiling: None,
+        }
+    }
+
+    #[test]
+    fn test_mapping() {
+        let mapping = |config| WallMapping::from_config(config);
+        let default = WallMappingConfig::default();
+        assert_eq!(mapping(default).uv(1.25, 0.5), (0.25, 0.5));
+        // Walls shorter than the texture only show part of it
+        let scaled = WallMappingConfig {
+            scale: 2.0,
+            offset: Vector2::new(0.5, 0.0),
+            ..default
+        };
+        assert_eq!(mapping(scaled).uv(0.25, 0.5), (0.375, 0.25));
+        let flipped = WallMappingConfig {
+            flip_horizontal: true,
+            flip_vertical: true,
+            ..default
+        };
+        assert_eq!(mapping(flipped).uv(0.25, 0.5), (0.75, 0.5));
+        let rotated = WallMappingConfig {
+            rotate: true,
+            ..default
+        };
+        assert_eq!(mapping(rotated).uv(0.25, 0.5), (0.5, 0.75));
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mirror = Mirror::from_config(MirrorConfig {
+            tint: Color::new(255, 128, 0, 255),
+            attenuation: 0.5,
+        });
+        assert_eq!(
+            mirror.reflect(Color::new(200, 200, 200, 255)),
+            Color::new(100, 50, 0, 255)
+        );
+    }
+
+    #[test]
+    fn test_portal() {
+        let entrance = WallConfig {
+            portal: Some("exit".into()),
+            ..wall(0.0, 1.0)
+        };
+        let exit = WallConfig {
+            id: Some("exit".into()),
+            bottom: 1.0,
+            top: 2.0,
+            ..wall(10.0, 2.0)
+        };
+        let mut map = Map::from_lines(vec![entrance, exit, wall(5.0, 1.0)]);
+        let portal = map.walls[0].portal.unwrap();
+        assert_eq!(portal.target, 1);
+        assert!(map.walls[1].portal.is_none());
+        assert_eq!(portal.elevation, 1.0);
+
+        // Walking through the entrance comes back out of the exit
+        assert_close(
+            portal.point(&Vector2::new(-0.5, 0.5)),
+            Vector2::new(10.5, -0.5),
+        );
+        assert_close(
+            portal.point(&Vector2::new(0.5, 0.0)),
+            Vector2::new(9.5, 0.0),
+        );
+        assert_close(
+            portal.direction(&Vector2::new(1.0, 0.0)),
+            Vector2::new(-1.0, 0.0),
+        );
+
+        // The portal follows the exit when it moves
+        let transform = Transform {
+            angle: 0.0,
+            translation: Vector2::new(0.0, 5.0),
+        };
+        let shape = map.walls[1].shape.clone();
+        map.move_wall(1, Arc::new(Transformed::new(shape, transform)));
+        assert_close(
+            map.walls[0].portal.unwrap().point(&Vector2::new(-0.5, 0.5)),
+            Vector2::new(10.5, 4.5),
+        );
+    }
+
+    #[test]
+    fn test_sectors() {
+        let map = Map::from_config(MapConfig {
+            walls: Vec::new(),
+            sectors: vec![
+                sector(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)], 0.0, 2.0),
+                // Clockwise, sharing its left edge with the first sector
+                sector(&[(2.0, 0.0), (2.0, 2.0), (4.0, 2.0), (4.0, 0.0)], 0.5, 1.5),
+            ],
+        });
+
+        // The shared edge only becomes one wall
+        assert_eq!(map.walls.len(), 7);
+        let shared = map
+            .walls
+            .iter()
+            .filter_map(|wall| wall.opening.map(|opening| (wall, opening)))
+            .collect::<Vec<(&Wall, Opening)>>();
+        assert_eq!(shared.len(), 1);
+        let (wall, opening) = shared[0];
+        assert_eq!((wall.bottom, wall.top), (0.0, 2.0));
+        assert_eq!((opening.bottom, opening.top), (0.5, 1.5));
+
+        let entered = |origin: Vector2, direction: Vector2| {
+            let hit = wall.shape.cast(&Ray::new(origin, direction)).unwrap();
+            opening.entered_sector(hit.side)
+        };
+        assert_eq!(entered(Vector2::new(1.0, 1.0), Vector2::new(1.0, 0.0)), 1);
+        assert_eq!(entered(Vector2::new(3.0, 1.0), Vector2::new(-1.0, 0.0)), 0);
+
+        assert_eq!(
+            map.sector_at(&Vector2::new(3.0, 1.0)).unwrap().floor_height,
+            0.5
+        );
+        assert!(map.sector_at(&Vector2::new(5.0, 1.0)).is_none());
     }
 }
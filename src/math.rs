@@ -1,11 +1,11 @@
 use std::fmt::Display;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 #[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
 pub struct Vector2 {
-    x: f32,
-    y: f32,
+    pub x: f32,
+    pub y: f32,
 }
 
 impl Display for Vector2 {
@@ -59,6 +59,17 @@ impl Vector2 {
     pub fn cross(&self, other: &Vector2) -> f32 {
         self.x * other.y - self.y * other.x
     }
+
+    pub fn dot(&self, other: &Vector2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+}
+
+/// Side of a line, looking from its start towards its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -66,11 +77,178 @@ pub struct Intersection {
     pub distance: f32,
     pub position: Vector2,
     pub offset: f32, // Value between 0 and 1
+    /// Unit normal of the side that was hit, pointing back towards the ray's origin
+    pub normal: Vector2,
+    pub side: Side,
 }
 
 pub mod geometry {
     use super::*;
 
+    // Margin around bounding boxes so that rays grazing them still count
+    const BOUNDS_MARGIN: f32 = 1e-4;
+
+    /// Shape walls can be made of, the offset along it going from 0 at its
+    /// start to 1 at its end
+    pub trait Castable: std::fmt::Debug + Send + Sync {
+        /// Nearest intersection with the ray
+        fn cast(&self, ray: &Ray) -> Option<Intersection>;
+
+        /// Axis-aligned box the shape lies in
+        fn bounds(&self) -> Bounds;
+
+        /// Point of the shape nearest to `point`
+        fn closest_point(&self, point: &Vector2) -> Vector2;
+
+        /// Unit vector perpendicular to the shape at the offset, pointing to
+        /// its left
+        fn normal(&self, offset: f32) -> Vector2;
+
+        fn length(&self) -> f32;
+
+        fn point(&self, offset: f32) -> Vector2;
+    }
+
+    /// Axis-aligned bounding box
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Bounds {
+        pub min: Vector2,
+        pub max: Vector2,
+    }
+
+    impl Bounds {
+        pub fn around(points: impl IntoIterator<Item = Vector2>) -> Self {
+            points.into_iter().fold(
+                Self {
+                    min: Vector2::new(f32::INFINITY, f32::INFINITY),
+                    max: Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+                },
+                |bounds, point| Self {
+                    min: Vector2::new(bounds.min.x.min(point.x), bounds.min.y.min(point.y)),
+                    max: Vector2::new(bounds.max.x.max(point.x), bounds.max.y.max(point.y)),
+                },
+            )
+        }
+
+        /// Whether the ray goes through the box, checked one axis at a time
+        pub fn is_hit_by(&self, ray: &Ray) -> bool {
+            let (mut near, mut far) = (0.0_f32, f32::INFINITY);
+            for (origin, direction, min, max) in [
+                (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+                (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            ] {
+                let (min, max) = (min - BOUNDS_MARGIN, max + BOUNDS_MARGIN);
+                if direction.abs() < f32::EPSILON {
+                    if origin < min || origin > max {
+                        return false;
+                    }
+                    continue;
+                }
+                let (a, b) = ((min - origin) / direction, (max - origin) / direction);
+                near = near.max(a.min(b));
+                far = far.min(a.max(b));
+            }
+            near <= far
+        }
+    }
+
+    /// Rotation around the origin followed by a translation
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    pub struct Transform {
+        /// Counterclockwise, in radians
+        pub angle: f32,
+        pub translation: Vector2,
+    }
+
+    impl Transform {
+        /// Rotation by `angle` radians around the `pivot`, then translation
+        pub fn around(pivot: &Vector2, angle: f32, translation: &Vector2) -> Self {
+            Self {
+                angle,
+                translation: pivot.subtract(&pivot.rotate(angle)).add(translation),
+            }
+        }
+
+        pub fn apply(&self, point: &Vector2) -> Vector2 {
+            point.rotate(self.angle).add(&self.translation)
+        }
+
+        pub fn apply_direction(&self, direction: &Vector2) -> Vector2 {
+            direction.rotate(self.angle)
+        }
+
+        pub fn inverse(&self) -> Self {
+            Self {
+                angle: -self.angle,
+                translation: self.translation.multiply(-1.0).rotate(-self.angle),
+            }
+        }
+    }
+
+    /// Shape moved by a transform, rays being brought back into the shape's
+    /// own space to be cast
+    #[derive(Debug, Clone)]
+    pub struct Transformed {
+        shape: std::sync::Arc<dyn Castable>,
+        transform: Transform,
+        inverse: Transform,
+    }
+
+    impl Transformed {
+        pub fn new(shape: std::sync::Arc<dyn Castable>, transform: Transform) -> Self {
+            Self {
+                shape,
+                inverse: transform.inverse(),
+                transform,
+            }
+        }
+    }
+
+    impl Castable for Transformed {
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            let local = Ray::new(
+                self.inverse.apply(&ray.origin),
+                self.inverse.apply_direction(&ray.direction),
+            );
+            // Rotations keep distances and sides unchanged
+            self.shape.cast(&local).map(|intersection| Intersection {
+                position: self.transform.apply(&intersection.position),
+                normal: self.transform.apply_direction(&intersection.normal),
+                ..intersection
+            })
+        }
+
+        fn bounds(&self) -> Bounds {
+            let Bounds { min, max } = self.shape.bounds();
+            Bounds::around(
+                [
+                    min,
+                    Vector2::new(min.x, max.y),
+                    max,
+                    Vector2::new(max.x, min.y),
+                ]
+                .map(|corner| self.transform.apply(&corner)),
+            )
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            self.transform
+                .apply(&self.shape.closest_point(&self.inverse.apply(point)))
+        }
+
+        fn normal(&self, offset: f32) -> Vector2 {
+            self.transform.apply_direction(&self.shape.normal(offset))
+        }
+
+        fn length(&self) -> f32 {
+            self.shape.length()
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            self.transform.apply(&self.shape.point(offset))
+        }
+    }
+
     #[derive(Debug, Clone, Copy, Deserialize)]
     pub struct Line {
         pub start: Vector2,
@@ -98,16 +276,509 @@ pub mod geometry {
             if t >= 0.0 && u >= 0.0 && u <= 1.0 {
                 let intersection_point = p0.add(&d.multiply(t));
                 let distance = t * ray.direction.magnitude(); // Distance along the ray
+                let normal = self.normal();
+                let (normal, side) = if normal.dot(&d) > 0.0 {
+                    (normal.multiply(-1.0), Side::Right)
+                } else {
+                    (normal, Side::Left)
+                };
 
                 Some(Intersection {
                     distance,
                     position: intersection_point,
                     offset: u,
+                    normal,
+                    side,
                 })
             } else {
                 None
             }
         }
+
+        /// Unit vector perpendicular to the line, pointing to its left
+        pub fn normal(&self) -> Vector2 {
+            let along = self.end.subtract(&self.start);
+            Vector2::new(-along.y, along.x).multiply(1.0 / along.magnitude())
+        }
+    }
+
+    impl Castable for Line {
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            Line::cast(self, ray)
+        }
+
+        fn bounds(&self) -> Bounds {
+            Bounds::around([self.start, self.end])
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            let along = self.end.subtract(&self.start);
+            let squared = along.dot(&along);
+            if squared < f32::EPSILON {
+                return self.start;
+            }
+            self.point((point.subtract(&self.start).dot(&along) / squared).clamp(0.0, 1.0))
+        }
+
+        fn normal(&self, _offset: f32) -> Vector2 {
+            Line::normal(self)
+        }
+
+        fn length(&self) -> f32 {
+            self.end.subtract(&self.start).magnitude()
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            self.start
+                .add(&self.end.subtract(&self.start).multiply(offset))
+        }
+    }
+
+    /// Full circle, solid walls being hit from outside and rooms from inside
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    pub struct Circle {
+        pub center: Vector2,
+        pub radius: f32,
+    }
+
+    /// Part of a circle going counterclockwise from the `start` angle to the
+    /// `end` one, in degrees, the center being on its left
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    pub struct Arc {
+        pub center: Vector2,
+        pub radius: f32,
+        pub start: f32,
+        pub end: f32,
+    }
+
+    // Distances along the ray, nearest first, of its intersections with a
+    // circle, behind the origin or not
+    fn circle_roots(center: &Vector2, radius: f32, ray: &Ray) -> Option<[f32; 2]> {
+        let d = ray.direction;
+        let to_origin = ray.origin.subtract(center);
+        let a = d.dot(&d);
+        let b = to_origin.dot(&d);
+        let c = to_origin.dot(&to_origin) - radius * radius;
+        let discriminant = b * b - a * c;
+        if a < f32::EPSILON || discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        Some([(-b - root) / a, (-b + root) / a])
+    }
+
+    // Intersection with a circle at the ray parameter `t`, the outside being
+    // the right side, like for a counterclockwise line around the center
+    fn circle_intersection(center: &Vector2, ray: &Ray, t: f32, offset: f32) -> Intersection {
+        let position = ray.origin.add(&ray.direction.multiply(t));
+        let outward = position
+            .subtract(center)
+            .multiply(1.0 / position.subtract(center).magnitude());
+        let (normal, side) = if outward.dot(&ray.direction) > 0.0 {
+            (outward.multiply(-1.0), Side::Left)
+        } else {
+            (outward, Side::Right)
+        };
+        Intersection {
+            distance: t * ray.direction.magnitude(),
+            position,
+            offset,
+            normal,
+            side,
+        }
+    }
+
+    // Point of a circle nearest to `point`, any point of it for the center
+    fn circle_closest_point(center: &Vector2, radius: f32, point: &Vector2) -> Vector2 {
+        let relative = point.subtract(center);
+        let distance = relative.magnitude();
+        if distance < f32::EPSILON {
+            return center.add(&Vector2::new(radius, 0.0));
+        }
+        center.add(&relative.multiply(radius / distance))
+    }
+
+    // Unit vector from a point of a circle to its center
+    fn inward(center: &Vector2, point: &Vector2) -> Vector2 {
+        let inward = center.subtract(point);
+        inward.multiply(1.0 / inward.magnitude())
+    }
+
+    fn circle_bounds(center: &Vector2, radius: f32) -> Bounds {
+        let corner = Vector2::new(radius, radius);
+        Bounds {
+            min: center.subtract(&corner),
+            max: center.add(&corner),
+        }
+    }
+
+    fn angle_of(center: &Vector2, point: &Vector2) -> f32 {
+        let relative = point.subtract(center);
+        relative
+            .y
+            .atan2(relative.x)
+            .rem_euclid(std::f32::consts::TAU)
+    }
+
+    /// Normals pointing towards the center and offsets being the angle of the
+    /// point from the center as a fraction of a full turn
+    impl Castable for Circle {
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            circle_roots(&self.center, self.radius, ray)?
+                .into_iter()
+                .find(|&t| t >= 0.0)
+                .map(|t| {
+                    let position = ray.origin.add(&ray.direction.multiply(t));
+                    let offset = angle_of(&self.center, &position) / std::f32::consts::TAU;
+                    circle_intersection(&self.center, ray, t, offset)
+                })
+        }
+
+        fn bounds(&self) -> Bounds {
+            circle_bounds(&self.center, self.radius)
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            circle_closest_point(&self.center, self.radius, point)
+        }
+
+        fn normal(&self, offset: f32) -> Vector2 {
+            inward(&self.center, &self.point(offset))
+        }
+
+        fn length(&self) -> f32 {
+            std::f32::consts::TAU * self.radius
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            let angle = offset * std::f32::consts::TAU;
+            self.center
+                .add(&Vector2::new(angle.cos(), angle.sin()).multiply(self.radius))
+        }
+    }
+
+    impl Arc {
+        // Angles in radians of the start of the arc and of its extent
+        fn angles(&self) -> (f32, f32) {
+            let start = self.start.to_radians().rem_euclid(std::f32::consts::TAU);
+            let mut span = (self.end - self.start).rem_euclid(360.0);
+            // Going from 0 to 360 degrees is a full turn, not an empty arc
+            if span == 0.0 && self.end != self.start {
+                span = 360.0;
+            }
+            (start, span.to_radians())
+        }
+    }
+
+    /// Normals pointing towards the center and offsets being the angle of the
+    /// point from the start of the arc as a fraction of the arc's angle
+    impl Castable for Arc {
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            let (start, span) = self.angles();
+            circle_roots(&self.center, self.radius, ray)?
+                .into_iter()
+                .filter(|&t| t >= 0.0)
+                .find_map(|t| {
+                    let position = ray.origin.add(&ray.direction.multiply(t));
+                    let angle = (angle_of(&self.center, &position) - start)
+                        .rem_euclid(std::f32::consts::TAU);
+                    (angle <= span).then(|| circle_intersection(&self.center, ray, t, angle / span))
+                })
+        }
+
+        // The whole circle is a bit more than needed
+        fn bounds(&self) -> Bounds {
+            circle_bounds(&self.center, self.radius)
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            let (start, span) = self.angles();
+            let angle = (angle_of(&self.center, point) - start).rem_euclid(std::f32::consts::TAU);
+            if angle <= span {
+                return circle_closest_point(&self.center, self.radius, point);
+            }
+            let (first, last) = (self.point(0.0), self.point(1.0));
+            if first.subtract(point).magnitude() < last.subtract(point).magnitude() {
+                first
+            } else {
+                last
+            }
+        }
+
+        fn normal(&self, offset: f32) -> Vector2 {
+            inward(&self.center, &self.point(offset))
+        }
+
+        fn length(&self) -> f32 {
+            self.angles().1 * self.radius
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            let (start, span) = self.angles();
+            let angle = start + offset * span;
+            self.center
+                .add(&Vector2::new(angle.cos(), angle.sin()).multiply(self.radius))
+        }
+    }
+
+    // Largest distance allowed between a curve and the segments approximating it
+    const CURVE_TOLERANCE: f32 = 1e-2;
+    // Bounds to the number of times the parameter range of a curve is halved
+    // when approximating it, curves bending the other way around the middle
+    // of the range not being noticed before a couple of halvings
+    const MIN_SUBDIVISIONS: u32 = 2;
+    const MAX_SUBDIVISIONS: u32 = 8;
+
+    /// Curve approximated by segments when loaded, the offset along it
+    /// following its length so that textures keep their proportions
+    #[derive(Debug, Clone)]
+    pub struct Curve {
+        points: Vec<Vector2>,
+        // Length of the curve up to each point
+        lengths: Vec<f32>,
+    }
+
+    // Appends the points approximating the curve between the parameters
+    // `from` (excluded) and `to`
+    fn tessellate(
+        curve: &impl Fn(f32) -> Vector2,
+        from: f32,
+        to: f32,
+        depth: u32,
+        points: &mut Vec<Vector2>,
+    ) {
+        let middle = (from + to) / 2.0;
+        let chord_middle = curve(from).add(&curve(to)).multiply(0.5);
+        let flat = curve(middle).subtract(&chord_middle).magnitude() <= CURVE_TOLERANCE;
+        if depth < MIN_SUBDIVISIONS || (!flat && depth < MAX_SUBDIVISIONS) {
+            tessellate(curve, from, middle, depth + 1, points);
+            tessellate(curve, middle, to, depth + 1, points);
+        } else {
+            points.push(curve(to));
+        }
+    }
+
+    impl Curve {
+        fn from_points(points: Vec<Vector2>) -> Self {
+            let lengths = std::iter::once(0.0)
+                .chain(points.windows(2).scan(0.0, |length, pair| {
+                    *length += pair[1].subtract(&pair[0]).magnitude();
+                    Some(*length)
+                }))
+                .collect();
+            Self { points, lengths }
+        }
+
+        /// Quadratic or cubic Bezier curve from its 3 or 4 control points
+        pub fn bezier(control: &[Vector2]) -> Result<Self, String> {
+            if !(3..=4).contains(&control.len()) {
+                return Err(format!(
+                    "A Bezier curve needs 3 or 4 control points, not {}",
+                    control.len()
+                ));
+            }
+            // De Casteljau's algorithm
+            let curve = |t: f32| {
+                let mut points = control.to_vec();
+                while points.len() > 1 {
+                    points = points
+                        .windows(2)
+                        .map(|pair| pair[0].add(&pair[1].subtract(&pair[0]).multiply(t)))
+                        .collect();
+                }
+                points[0]
+            };
+            let mut points = vec![control[0]];
+            tessellate(&curve, 0.0, 1.0, 0, &mut points);
+            Ok(Self::from_points(points))
+        }
+
+        /// Catmull-Rom spline going through every point
+        pub fn catmull_rom(through: &[Vector2]) -> Result<Self, String> {
+            if through.len() < 2 {
+                return Err(format!(
+                    "A spline needs at least 2 points, not {}",
+                    through.len()
+                ));
+            }
+            let last = through.len() - 1;
+            let mut points = vec![through[0]];
+            for i in 0..last {
+                // The ends are repeated to have neighbours on both sides
+                let neighbours = [
+                    through[i.saturating_sub(1)],
+                    through[i],
+                    through[i + 1],
+                    through[(i + 2).min(last)],
+                ];
+                let curve = |t: f32| {
+                    let (t2, t3) = (t * t, t * t * t);
+                    [
+                        -t + 2.0 * t2 - t3,
+                        2.0 - 5.0 * t2 + 3.0 * t3,
+                        t + 4.0 * t2 - 3.0 * t3,
+                        -t2 + t3,
+                    ]
+                    .iter()
+                    .zip(&neighbours)
+                    .fold(Vector2::default(), |sum, (weight, point)| {
+                        sum.add(&point.multiply(0.5 * weight))
+                    })
+                };
+                tessellate(&curve, 0.0, 1.0, 0, &mut points);
+            }
+            Ok(Self::from_points(points))
+        }
+
+        fn segments(&self) -> impl Iterator<Item = Line> + '_ {
+            self.points.windows(2).map(|pair| Line {
+                start: pair[0],
+                end: pair[1],
+            })
+        }
+
+        // Segment where the offset lies and the offset along it
+        fn locate(&self, offset: f32) -> (Line, f32) {
+            let along = offset.clamp(0.0, 1.0) * self.length();
+            let i = self
+                .lengths
+                .partition_point(|&length| length <= along)
+                .clamp(1, self.points.len() - 1);
+            let segment = Line {
+                start: self.points[i - 1],
+                end: self.points[i],
+            };
+            let length = self.lengths[i] - self.lengths[i - 1];
+            let offset = if length > 0.0 {
+                (along - self.lengths[i - 1]) / length
+            } else {
+                0.0
+            };
+            (segment, offset)
+        }
+    }
+
+    impl Castable for Curve {
+        /// Nearest intersection with one of the segments
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            self.segments()
+                .enumerate()
+                .filter_map(|(i, segment)| {
+                    segment.cast(ray).map(|intersection| Intersection {
+                        offset: (self.lengths[i] + intersection.offset * segment.length())
+                            / self.length(),
+                        ..intersection
+                    })
+                })
+                .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        }
+
+        fn bounds(&self) -> Bounds {
+            Bounds::around(self.points.iter().copied())
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            self.segments()
+                .map(|segment| segment.closest_point(point))
+                .min_by(|a, b| {
+                    let distance = |closest: &Vector2| closest.subtract(point).magnitude();
+                    distance(a).total_cmp(&distance(b))
+                })
+                .unwrap_or(self.points[0])
+        }
+
+        fn normal(&self, offset: f32) -> Vector2 {
+            Line::normal(&self.locate(offset).0)
+        }
+
+        fn length(&self) -> f32 {
+            self.lengths.last().copied().unwrap_or(0.0)
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            let (segment, offset) = self.locate(offset);
+            segment.point(offset)
+        }
+    }
+
+    fn bezier<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Curve, D::Error> {
+        Curve::bezier(&Vec::<Vector2>::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+
+    fn spline<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Curve, D::Error> {
+        Curve::catmull_rom(&Vec::<Vector2>::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+
+    /// Shapes walls can be made of in scene files
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub enum Shape {
+        Line(Line),
+        Circle(Circle),
+        Arc(Arc),
+        /// Quadratic or cubic Bezier curve from its control points
+        #[serde(deserialize_with = "bezier")]
+        Bezier(Curve),
+        /// Catmull-Rom spline through a list of points
+        #[serde(deserialize_with = "spline")]
+        Spline(Curve),
+    }
+
+    impl Shape {
+        pub fn into_castable(self) -> std::sync::Arc<dyn Castable> {
+            match self {
+                Shape::Line(line) => std::sync::Arc::new(line),
+                Shape::Circle(circle) => std::sync::Arc::new(circle),
+                Shape::Arc(arc) => std::sync::Arc::new(arc),
+                Shape::Bezier(curve) | Shape::Spline(curve) => std::sync::Arc::new(curve),
+            }
+        }
+    }
+
+    /// Closed polygon, the last point being linked back to the first one
+    #[derive(Debug, Clone, Default)]
+    pub struct Polygon {
+        pub points: Vec<Vector2>,
+    }
+
+    impl Polygon {
+        pub fn new(points: Vec<Vector2>) -> Self {
+            Self { points }
+        }
+
+        /// Positive when the points go counterclockwise
+        pub fn signed_area(&self) -> f32 {
+            self.edges()
+                .map(|edge| edge.start.cross(&edge.end))
+                .sum::<f32>()
+                / 2.0
+        }
+
+        pub fn edges(&self) -> impl Iterator<Item = Line> + '_ {
+            self.points
+                .iter()
+                .zip(self.points.iter().cycle().skip(1))
+                .map(|(start, end)| Line {
+                    start: *start,
+                    end: *end,
+                })
+        }
+
+        // Even-odd rule: a point is inside if a ray starting from it crosses an odd number of edges
+        pub fn contains(&self, point: &Vector2) -> bool {
+            self.edges()
+                .filter(|edge| {
+                    (edge.start.y > point.y) != (edge.end.y > point.y)
+                        && point.x
+                            < edge.start.x
+                                + (point.y - edge.start.y) * (edge.end.x - edge.start.x)
+                                    / (edge.end.y - edge.start.y)
+                })
+                .count()
+                % 2
+                == 1
+        }
     }
 }
 
@@ -137,6 +808,12 @@ impl Ray {
     }
 }
 
+/// Asserts that two points are the same but for rounding errors
+#[cfg(test)]
+pub fn assert_close(a: Vector2, b: Vector2) {
+    assert!(a.subtract(&b).magnitude() < 1e-5, "{a:?} != {b:?}");
+}
+
 #[cfg(test)]
 mod tests {
     use super::{geometry::*, *};
@@ -270,6 +947,24 @@ mod tests {
         assert_eq!(position.y, 1.0000001);
     }
 
+    #[test]
+    fn test_polygon() {
+        let square = Polygon::new(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(0.0, 2.0),
+        ]);
+        assert_eq!(square.signed_area(), 4.0);
+        assert_eq!(square.edges().count(), 4);
+        assert!(square.contains(&Vector2::new(1.0, 1.0)));
+        assert!(!square.contains(&Vector2::new(3.0, 1.0)));
+        assert!(!square.contains(&Vector2::new(1.0, -1.0)));
+
+        let reversed = Polygon::new(square.points.into_iter().rev().collect());
+        assert_eq!(reversed.signed_area(), -4.0);
+    }
+
     #[test]
     fn test_intersection_offset() {
         let ray = Ray {
@@ -286,4 +981,190 @@ mod tests {
         let Intersection { offset, .. } = intersection.unwrap();
         assert_eq!(offset, 0.5);
     }
+
+    #[test]
+    fn test_intersection_side() {
+        let line = Line {
+            start: Vector2::new(1.0, -1.0),
+            end: Vector2::new(1.0, 1.0),
+        };
+
+        let Intersection { normal, side, .. } = line
+            .cast(&Ray::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)))
+            .unwrap();
+        assert_eq!(side, Side::Left);
+        assert_eq!(normal, Vector2::new(-1.0, 0.0));
+
+        let Intersection { normal, side, .. } = line
+            .cast(&Ray::new(Vector2::new(2.0, 0.0), Vector2::new(-1.0, 0.0)))
+            .unwrap();
+        assert_eq!(side, Side::Right);
+        assert_eq!(normal, Vector2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_circle() {
+        let circle = Circle {
+            center: Vector2::new(3.0, 0.0),
+            radius: 1.0,
+        };
+        let outside = Ray::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0));
+        let Intersection {
+            distance,
+            offset,
+            normal,
+            side,
+            ..
+        } = circle.cast(&outside).unwrap();
+        assert_eq!((distance, offset, side), (2.0, 0.5, Side::Right));
+        assert_eq!(normal, Vector2::new(-1.0, 0.0));
+
+        let inside = Ray::new(Vector2::new(3.0, 0.0), Vector2::new(0.0, 2.0));
+        let Intersection {
+            distance,
+            offset,
+            side,
+            ..
+        } = circle.cast(&inside).unwrap();
+        assert_eq!((distance, offset, side), (1.0, 0.25, Side::Left));
+
+        let above = Ray::new(Vector2::new(0.0, 2.0), Vector2::new(1.0, 0.0));
+        assert!(circle.cast(&above).is_none());
+        assert!((circle.length() - std::f32::consts::TAU).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_arc() {
+        // Upper half of the circle of radius 2 around the origin
+        let arc = Arc {
+            center: Vector2::new(0.0, 0.0),
+            radius: 2.0,
+            start: 0.0,
+            end: 180.0,
+        };
+        let up = Ray::new(Vector2::new(1.0, -5.0), Vector2::new(0.0, 1.0));
+        let Intersection {
+            position, offset, ..
+        } = arc.cast(&up).unwrap();
+        // The lower half is open, the ray only hitting the far side
+        assert!((position.y - 3f32.sqrt()).abs() < 1e-5);
+        assert!((offset - 1.0 / 3.0).abs() < 1e-5);
+        let down = Ray::new(Vector2::new(1.0, -1.0), Vector2::new(0.0, -1.0));
+        assert!(arc.cast(&down).is_none());
+
+        assert!((arc.length() - 2.0 * std::f32::consts::PI).abs() < 1e-5);
+        let top = arc.point(0.5);
+        assert_close(top, Vector2::new(0.0, 2.0));
+        assert!(
+            arc.normal(0.5)
+                .subtract(&Vector2::new(0.0, -1.0))
+                .magnitude()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn test_castable() {
+        let shapes: Vec<Box<dyn Castable>> = vec![
+            Box::new(Line {
+                start: Vector2::new(-1.0, 2.0),
+                end: Vector2::new(1.0, 2.0),
+            }),
+            Box::new(Arc {
+                center: Vector2::new(0.0, 0.0),
+                radius: 2.0,
+                start: 0.0,
+                end: 180.0,
+            }),
+        ];
+        for shape in &shapes {
+            let closest = shape.closest_point(&Vector2::new(0.0, 5.0));
+            assert_close(closest, Vector2::new(0.0, 2.0));
+            assert!(shape
+                .bounds()
+                .is_hit_by(&Ray::new(Vector2::new(0.0, 5.0), Vector2::new(0.0, -1.0))));
+            assert!(!shape
+                .bounds()
+                .is_hit_by(&Ray::new(Vector2::new(0.0, 5.0), Vector2::new(0.0, 1.0))));
+        }
+        // Below the arc, its nearest end is the closest
+        let closest = shapes[1].closest_point(&Vector2::new(3.0, -1.0));
+        assert_close(closest, Vector2::new(2.0, 0.0));
+        assert_eq!(
+            shapes[0].bounds(),
+            Bounds {
+                min: Vector2::new(-1.0, 2.0),
+                max: Vector2::new(1.0, 2.0)
+            }
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let pivot = Vector2::new(1.0, 1.0);
+        let transform = Transform::around(&pivot, 90f32.to_radians(), &Vector2::new(0.0, 1.0));
+        let point = transform.apply(&Vector2::new(2.0, 1.0));
+        assert_close(point, Vector2::new(1.0, 3.0));
+        let back = transform.inverse().apply(&point);
+        assert_close(back, Vector2::new(2.0, 1.0));
+
+        let line = Transformed::new(
+            std::sync::Arc::new(Line {
+                start: Vector2::new(1.0, 1.0),
+                end: Vector2::new(3.0, 1.0),
+            }),
+            transform,
+        );
+        let Intersection {
+            distance, offset, ..
+        } = line
+            .cast(&Ray::new(Vector2::new(0.0, 3.0), Vector2::new(1.0, 0.0)))
+            .unwrap();
+        assert!((distance - 1.0).abs() < 1e-5);
+        assert!((offset - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_curves() {
+        let close = |a: Vector2, b: Vector2| a.subtract(&b).magnitude() < 1e-3;
+        // Symmetric arch going from (0, 0) to (2, 0) through (1, 1)
+        let arch = Curve::bezier(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 2.0),
+            Vector2::new(2.0, 0.0),
+        ])
+        .unwrap();
+        assert!(close(arch.point(0.0), Vector2::new(0.0, 0.0)));
+        assert!(close(arch.point(0.5), Vector2::new(1.0, 1.0)));
+        assert!(close(arch.point(1.0), Vector2::new(2.0, 0.0)));
+        // Pointing up, towards the inside of the arch
+        assert!(arch.normal(0.5).y > 0.9);
+        let hit = arch
+            .cast(&Ray::new(Vector2::new(1.0, 3.0), Vector2::new(0.0, -1.0)))
+            .unwrap();
+        assert!((hit.distance - 2.0).abs() < 1e-3);
+        assert!((hit.offset - 0.5).abs() < 1e-3);
+        // The offset follows the length rather than the curve's parameter
+        let hit = arch
+            .cast(&Ray::new(Vector2::new(0.5, 3.0), Vector2::new(0.0, -1.0)))
+            .unwrap();
+        assert!(hit.offset > 0.25 && hit.offset < 0.5);
+        assert!(Curve::bezier(&[Vector2::default(); 2]).is_err());
+
+        let through = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(3.0, 1.0),
+        ];
+        let spline = Curve::catmull_rom(&through).unwrap();
+        assert!(close(spline.point(0.0), through[0]));
+        assert!(close(spline.point(1.0), through[3]));
+        let hit = spline
+            .cast(&Ray::new(Vector2::new(1.0, 3.0), Vector2::new(0.0, -1.0)))
+            .unwrap();
+        assert!(close(hit.position, through[1]));
+        assert!(spline.length() > 3.0 * 2f32.sqrt());
+        assert!(Curve::catmull_rom(&through[..1]).is_err());
+    }
 }
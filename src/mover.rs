@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use log::error;
+
+use crate::{
+    config::{Easing, KeyframeConfig, LoopMode, MoverConfig},
+    map::Map,
+    math::{
+        geometry::{Bounds, Castable, Transform, Transformed},
+        Vector2,
+    },
+};
+
+/// Walls of the map moving together along keyframes
+#[derive(Debug, Clone)]
+pub struct Mover {
+    // Indices of the walls in the map, along with their shapes in the map
+    walls: Vec<(usize, Arc<dyn Castable>)>,
+    pivot: Vector2,
+    // Sorted by time
+    keyframes: Vec<KeyframeConfig>,
+    mode: LoopMode,
+}
+
+fn ease(easing: Easing, t: f32) -> f32 {
+    match easing {
+        Easing::Linear => t,
+        Easing::EaseIn => t * t,
+        Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+        Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+}
+
+impl Mover {
+    /// Mover of the walls named in `config`, leaving out the `moved` walls
+    /// already following a door or another mover
+    pub fn from_config(config: MoverConfig, map: &Map, moved: &[usize]) -> Option<Self> {
+        let walls = config
+            .walls
+            .iter()
+            .filter_map(|id| match map.wall_index(id) {
+                Some(index) if moved.contains(&index) => {
+                    error!("Moving a wall already moved: {id}");
+                    None
+                }
+                Some(index) => Some((index, map.walls[index].shape.clone())),
+                None => {
+                    error!("Moving unknown wall: {id}");
+                    None
+                }
+            })
+            .collect::<Vec<(usize, Arc<dyn Castable>)>>();
+        if walls.is_empty() || config.keyframes.is_empty() {
+            error!("Mover without walls or keyframes: {:?}", config.walls);
+            return None;
+        }
+        let mut keyframes = config.keyframes;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let pivot = config.pivot.unwrap_or_else(|| {
+            let bounds = walls.iter().map(|(index, _)| map.walls[*index].bounds);
+            let Bounds { min, max } =
+                Bounds::around(bounds.flat_map(|bounds| [bounds.min, bounds.max]));
+            min.add(&max).multiply(0.5)
+        });
+        Some(Self {
+            walls,
+            pivot,
+            keyframes,
+            mode: config.mode,
+        })
+    }
+
+    /// Placement of the walls `time` seconds after the scene started
+    pub fn transform(&self, time: f32) -> Transform {
+        let start = self.keyframes[0].time;
+        let duration = self.keyframes[self.keyframes.len() - 1].time - start;
+        let elapsed = (time - start).max(0.0);
+        let elapsed = match self.mode {
+            _ if duration <= 0.0 => 0.0,
+            LoopMode::Loop => elapsed.rem_euclid(duration),
+            LoopMode::Once => elapsed.min(duration),
+            LoopMode::PingPong => {
+                let elapsed = elapsed.rem_euclid(2.0 * duration);
+                if elapsed > duration {
+                    2.0 * duration - elapsed
+                } else {
+                    elapsed
+                }
+            }
+        };
+        let time = start + elapsed;
+
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time)
+            .min(self.keyframes.len() - 1);
+        let (from, to) = (
+            &self.keyframes[next.saturating_sub(1)],
+            &self.keyframes[next],
+        );
+        let span = to.time - from.time;
+        let t = if span > 0.0 {
+            ease(from.easing, ((time - from.time) / span).clamp(0.0, 1.0))
+        } else {
+            1.0
+        };
+        let offset = from
+            .offset
+            .add(&to.offset.subtract(&from.offset).multiply(t));
+        let angle = from.angle + (to.angle - from.angle) * t;
+        Transform::around(&self.pivot, angle.to_radians(), &offset)
+    }
+
+    /// Moves the walls to where they are `time` seconds after the scene started
+    pub fn update(&self, time: f32, map: &mut Map) {
+        let transform = self.transform(time);
+        for (index, shape) in &self.walls {
+            map.move_wall(*index, Arc::new(Transformed::new(shape.clone(), transform)));
+        }
+    }
+
+    /// Indices of the walls in the map
+    pub fn walls(&self) -> impl Iterator<Item = usize> + '_ {
+        self.walls.iter().map(|(index, _)| *index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::WallConfig,
+        math::{assert_close, Ray},
+    };
+
+    fn keyframe(time: f32, offset: (f32, f32), angle: f32) -> KeyframeConfig {
+        KeyframeConfig {
+            time,
+            offset: Vector2::new(offset.0, offset.1),
+            angle,
+            easing: Easing::Linear,
+        }
+    }
+
+    fn mover(keyframes: Vec<KeyframeConfig>, mode: LoopMode) -> (Mover, Map) {
+        let map = Map::from_lines(vec![WallConfig {
+            id: Some("barrier".into()),
+            ..WallConfig::line(Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0))
+        }]);
+        let config = MoverConfig {
+            walls: vec!["barrier".into(), "unknown".into()],
+            pivot: None,
+            keyframes,
+            mode,
+        };
+        (Mover::from_config(config, &map, &[]).unwrap(), map)
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let (mover, mut map) = mover(
+            vec![
+                keyframe(2.0, (0.0, 4.0), 90.0),
+                keyframe(0.0, (0.0, 0.0), 0.0),
+            ],
+            LoopMode::PingPong,
+        );
+        // Turns around the middle of the wall while moving up
+        mover.update(1.0, &mut map);
+        assert_close(map.walls[0].shape.point(0.5), Vector2::new(0.0, 2.0));
+        assert_close(
+            map.walls[0].shape.point(1.0),
+            Vector2::new(45f32.to_radians().cos(), 2.0 + 45f32.to_radians().sin()),
+        );
+        mover.update(2.0, &mut map);
+        assert_close(map.walls[0].shape.point(1.0), Vector2::new(0.0, 5.0));
+        // Then comes back
+        mover.update(3.5, &mut map);
+        assert_close(map.walls[0].shape.point(0.5), Vector2::new(0.0, 1.0));
+        let ray = Ray::new(Vector2::new(-2.0, 1.0), Vector2::new(1.0, 0.0));
+        assert_eq!(map.cast_all(&ray).len(), 1);
+    }
+
+    #[test]
+    fn test_easing() {
+        let mut keyframes = vec![
+            keyframe(0.0, (0.0, 0.0), 0.0),
+            keyframe(1.0, (4.0, 0.0), 0.0),
+        ];
+        keyframes[0].easing = Easing::EaseIn;
+        let (mover, _) = mover(keyframes, LoopMode::Once);
+        assert_close(mover.transform(0.5).translation, Vector2::new(1.0, 0.0));
+        assert_close(mover.transform(10.0).translation, Vector2::new(4.0, 0.0));
+        assert_eq!(ease(Easing::EaseOut, 0.5), 0.75);
+        assert_eq!(ease(Easing::EaseInOut, 0.5), 0.5);
+    }
+}
@@ -1,42 +1,227 @@
 use rayon::prelude::*;
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::Path,
+};
 
 use log::{error, info};
-use raylib::color::Color;
 
 use crate::{
+    animation::Animation,
     camera::Camera,
-    config::{Config, SceneConfig},
-    game::{Command, Direction},
-    map::Map,
-    math::Ray,
+    color::Color,
+    command::{Command, Direction},
+    config::{
+        AtlasRegionConfig, BlendMode, Config, LightmapConfig, MapConfig, SceneConfig, SpriteAnchor,
+        TextureConfig,
+    },
+    door::Door,
+    fog::Fog,
+    framebuffer::Framebuffer,
+    light::{Light, Shading},
+    lightmap::Lightmap,
+    map::{Hit, Map, Sector, Wall},
+    math::{geometry::Polygon, Intersection, Ray, Side, Vector2},
+    mover::Mover,
+    sky::Sky,
+    sprite::Sprite,
+    surface::Surface,
     texture::Texture,
 };
 
+const CEILING_COLOR: Color = Color::LIGHTGRAY;
+const FLOOR_COLOR: Color = Color::BROWN;
+const CEILING_HEIGHT: f32 = 1.0;
+// Eye height change per second when crouching or standing up
+const CROUCH_SPEED: f32 = 2.0;
+const MAX_STEP_HEIGHT: f32 = 0.25;
+// How much further than the collision radius doors can be reached
+const USE_REACH: f32 = 0.5;
+// Moving along a wall closer than the collision radius keeps the same
+// distance to it, give or take rounding errors
+const COLLISION_TOLERANCE: f32 = 1e-4;
+const ALPHA_CUTOFF: u8 = 128;
+
+// What a column of pixels is looking through, `depth` being the distance
+// already travelled when the ray was reflected by mirrors or sent through
+// portals, `reflections` and `portals` counting them
+struct ColumnView<'a> {
+    ray: &'a Ray,
+    eye: f32,
+    horizon: f32,
+    depth: f32,
+    reflections: u32,
+    portals: u32,
+}
+
+impl ColumnView<'_> {
+    // The ray's direction has a forward component of one so the distance
+    // along it, unfolded through mirrors and portals, is also the
+    // perpendicular distance
+    fn distance(&self, hit: &Hit) -> f32 {
+        self.depth + hit.intersec.distance / self.ray.direction.magnitude()
+    }
+}
+
+// A column of pixels being drawn, along with the distance of what each one shows
+struct Column {
+    colors: Vec<Option<Color>>,
+    depths: Vec<f32>,
+    // Translucent colors in front of each pixel, nearest first
+    overlays: Vec<Vec<(f32, Color)>>,
+    uncovered: usize,
+}
+
+impl Column {
+    fn new(height: usize) -> Self {
+        Self {
+            colors: vec![None; height],
+            depths: vec![f32::INFINITY; height],
+            overlays: vec![Vec::new(); height],
+            uncovered: height,
+        }
+    }
+
+    fn fill(&mut self, y: usize, color: Color, depth: f32) {
+        self.colors[y] = Some(color);
+        self.depths[y] = depth;
+        self.uncovered -= 1;
+    }
+
+    // Blends the translucent colors back to front over what lies behind them
+    fn composite(self) -> Vec<Color> {
+        (0..self.colors.len())
+            .map(|y| self.composite_pixel(y))
+            .collect()
+    }
+
+    fn composite_pixel(&self, y: usize) -> Color {
+        self.overlays[y]
+            .iter()
+            .rev()
+            .filter(|(distance, _)| *distance < self.depths[y])
+            .fold(
+                self.colors[y].unwrap_or(FLOOR_COLOR),
+                |color, (_, overlay)| color.blend(overlay),
+            )
+    }
+}
+
+struct ProjectedSprite<'a> {
+    texture: &'a Texture,
+    depth: f32,
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+}
+
 pub struct Scene {
     config: Config,
     camera: Camera,
     map: Map,
     default_texture: Texture,
-    textures: HashMap<String, Texture>,
+    textures: HashMap<String, Animation>,
+    // Seconds since the scene started, animating the textures
+    time: f32,
+    fog: Option<Fog>,
+    outdoors: Sector,
+    sky: Option<Sky>,
+    sprites: Vec<Sprite>,
+    lights: Vec<Light>,
+    shading: Shading,
+    // Height of the player's feet, above the floor while in the air
+    feet: f32,
+    vertical_speed: f32,
+    // Current height of the eye above the feet, moving towards the standing
+    // or crouching height
+    height: f32,
+    crouching: bool,
+    doors: Vec<Door>,
+    movers: Vec<Mover>,
 }
 
 impl Scene {
     pub fn from_config(config: &Config, scene_config: SceneConfig) -> Self {
+        Self::with_walls(config, scene_config, Vec::new())
+    }
+
+    /// Scene whose map also holds `walls` built outside of the config, of
+    /// any shape, doors and movers finding them by id like the others
+    pub fn with_walls(config: &Config, scene_config: SceneConfig, walls: Vec<Wall>) -> Self {
+        let load = |path: &str| {
+            info!("Loading texture: {path}");
+            let path = Path::new(path);
+            if !path.exists() {
+                error!("Texture not found: {}", path.display());
+            }
+            Texture::from_png(path).unwrap_or_default()
+        };
+        // Atlases are loaded once however many textures they hold
+        let atlases = scene_config
+            .textures
+            .values()
+            .filter_map(|texture| match texture {
+                TextureConfig::Region(region) => Some(region.atlas.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .map(|path| (path.clone(), load(&path)))
+            .collect::<HashMap<String, Texture>>();
         let textures = scene_config
             .textures
             .into_iter()
-            .map(|(name, path)| {
-                info!("Loading texture: {path}");
-                let path = Path::new(&path);
-                if !path.exists() {
-                    error!("Texture not found: {}", path.display());
-                }
-                (name, Texture::from_png(&path).unwrap_or_default())
+            .map(|(name, texture)| {
+                let animation = match texture {
+                    TextureConfig::Image(path) => Animation::still(load(&path)),
+                    // Cropped so that filtering never reaches the neighbouring
+                    // textures, regions out of the atlas showing as missing
+                    TextureConfig::Region(AtlasRegionConfig {
+                        atlas,
+                        rect: [x, y, width, height],
+                    }) => {
+                        let texture = &atlases[&atlas];
+                        if width == 0
+                            || height == 0
+                            || x.saturating_add(width) > texture.width
+                            || y.saturating_add(height) > texture.height
+                        {
+                            error!(
+                                "Region of texture {name} does not fit in atlas {atlas}: {:?}",
+                                [x, y, width, height]
+                            );
+                            Animation::still(Texture::default())
+                        } else {
+                            Animation::still(texture.crop(x, y, width, height))
+                        }
+                    }
+                    TextureConfig::Animation(config) => Animation::from_config(config, load),
+                };
+                (name, animation)
             })
             .collect();
 
-        Self {
+        let mut map = Self::load_map(scene_config.map, walls, scene_config.lightmap.as_ref());
+        // A wall follows a single door or mover
+        let mut moved = Vec::new();
+        let mut doors = Vec::new();
+        for door in scene_config.doors {
+            if let Some(door) = Door::from_config(door, &map, &moved) {
+                moved.push(door.wall);
+                doors.push(door);
+            }
+        }
+        let mut movers = Vec::new();
+        for mover in scene_config.movers {
+            if let Some(mover) = Mover::from_config(mover, &map, &moved) {
+                moved.extend(mover.walls());
+                movers.push(mover);
+            }
+        }
+        movers.iter().for_each(|mover| mover.update(0.0, &mut map));
+        let mut scene = Self {
             config: config.clone(),
             camera: Camera::new(
                 scene_config.camera.position,
@@ -44,80 +229,1199 @@ impl Scene {
                 config.fov,
                 config.screen.width,
             ),
-            map: Map::from_lines(scene_config.map.walls),
+            map,
             default_texture: Texture::default(),
             textures,
+            time: 0.0,
+            fog: scene_config.fog.map(Fog::from_config),
+            outdoors: Sector {
+                polygon: Polygon::default(),
+                floor_height: 0.0,
+                ceiling_height: CEILING_HEIGHT,
+                floor: scene_config.floor.map(Surface::from_config),
+                ceiling: scene_config.ceiling.map(Surface::from_config),
+            },
+            sky: scene_config.sky.map(Sky::from_config),
+            sprites: scene_config
+                .sprites
+                .into_iter()
+                .map(Sprite::from_config)
+                .collect(),
+            shading: Shading::from_config(scene_config.shading, !scene_config.lights.is_empty()),
+            lights: scene_config
+                .lights
+                .into_iter()
+                .map(Light::from_config)
+                .collect(),
+            feet: 0.0,
+            vertical_speed: 0.0,
+            height: config.player.eye_height,
+            crouching: false,
+            doors,
+            movers,
+        };
+        scene.feet = scene.current_sector().floor_height;
+        scene.camera.eye_height = scene.feet + scene.height;
+        scene
+    }
+
+    // Builds the map, attaching the baked lighting to its walls if there is some
+    fn load_map(config: MapConfig, walls: Vec<Wall>, lightmap: Option<&LightmapConfig>) -> Map {
+        let mut map = Map::from_config(config);
+        map.walls.extend(walls);
+        let Some(lightmap_config) = lightmap else {
+            return map;
+        };
+        let path = Path::new(&lightmap_config.path);
+        if !path.exists() {
+            info!("Lightmap not baked yet: {}", path.display());
+            return map;
+        }
+        match Lightmap::load(path) {
+            Ok(lightmap) if lightmap.fits(&map, lightmap_config.resolution) => {
+                info!("Loading lightmap: {}", path.display());
+                map.walls
+                    .iter_mut()
+                    .zip(lightmap.walls)
+                    .for_each(|(wall, lightmap)| wall.lightmap = Some(lightmap));
+            }
+            Ok(_) => error!("Lightmap does not match the map: {}", path.display()),
+            Err(e) => error!("Cannot read lightmap {}: {e}", path.display()),
         }
+        map
+    }
+
+    /// Computes the lighting of the static lights for every wall
+    pub fn bake_lightmap(&self, resolution: f32, bounce: bool) -> Lightmap {
+        let lights = self
+            .lights
+            .iter()
+            .filter(|light| light.is_static)
+            .cloned()
+            .collect::<Vec<Light>>();
+        Lightmap::bake(
+            &self.map,
+            &lights,
+            |wall| {
+                let albedo = self.texture(&wall.texture_name).average();
+                let tint = wall.mapping.tint;
+                [
+                    albedo.r as f32 * tint.r as f32,
+                    albedo.g as f32 * tint.g as f32,
+                    albedo.b as f32 * tint.b as f32,
+                ]
+                .map(|c| c / (255.0 * 255.0))
+            },
+            resolution,
+            bounce,
+        )
     }
 
     pub fn handle_inputs(&mut self, inputs: &[Command]) {
-        inputs.into_iter().for_each(|input| match input {
+        // Crouching lasts as long as the command keeps being sent
+        self.crouching = false;
+        inputs.iter().for_each(|input| match input {
             Command::Move(direction, speed) if self.can_move(direction, *speed) => {
-                match direction {
-                    Direction::Forward => self
-                        .camera
-                        .translate(&self.camera.forward().multiply(*speed)),
-                    Direction::Backward => self
-                        .camera
-                        .translate(&self.camera.backward().multiply(*speed)),
-                    Direction::Left => self.camera.translate(&self.camera.left().multiply(*speed)),
-                    Direction::Right => {
-                        self.camera.translate(&self.camera.right().multiply(*speed))
-                    }
-                }
+                self.walk(&self.step(direction, *speed))
             }
             Command::Look(speed) => self.camera.rotate(*speed),
+            Command::Pitch(speed) => self.camera.tilt(*speed, self.config.player.max_pitch),
+            Command::Jump if self.on_ground() => {
+                self.vertical_speed = self.config.player.jump_speed
+            }
+            Command::Crouch => self.crouching = true,
+            Command::PlaceLight(index) => {
+                if let Some(light) = self.lights.get_mut(*index) {
+                    light.position = self.camera.pos;
+                    light.height = self.camera.eye_height;
+                }
+            }
+            Command::Use => {
+                // The nearest door within reach, in front of the player
+                let position = self.camera.pos;
+                let reach = self.config.player.collision_radius + USE_REACH;
+                let forward = self.camera.forward();
+                let map = &self.map;
+                let door = self
+                    .doors
+                    .iter_mut()
+                    .map(|door| {
+                        let closest = map.walls[door.wall].shape.closest_point(&position);
+                        (closest.subtract(&position), door)
+                    })
+                    .filter(|(offset, _)| offset.magnitude() <= reach && offset.dot(&forward) > 0.0)
+                    .min_by(|(a, _), (b, _)| a.magnitude().total_cmp(&b.magnitude()));
+                if let Some((_, door)) = door {
+                    door.activate();
+                }
+            }
             _ => {}
         });
     }
 
-    // TODO Can do better
-    fn can_move(&self, direction: &Direction, speed: f32) -> bool {
-        let direction = match direction {
+    /// Advances the animations and moves the player vertically: falling,
+    /// landing, stepping up and crouching
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        let player = self.config.player;
+        let position = self.camera.pos;
+        for door in &mut self.doors {
+            door.update(delta, &mut self.map, |wall| {
+                let closest = wall.shape.closest_point(&position);
+                closest.subtract(&position).magnitude() + COLLISION_TOLERANCE
+                    < player.collision_radius
+            });
+        }
+        for mover in &self.movers {
+            mover.update(self.time, &mut self.map);
+        }
+        // Moving walls that do not stop for the player push them out of their way
+        let pushing = self
+            .doors
+            .iter()
+            .filter(|door| !door.blocking)
+            .map(|door| door.wall)
+            .chain(self.movers.iter().flat_map(Mover::walls))
+            .collect::<Vec<usize>>();
+        for index in pushing {
+            let position = self.camera.pos;
+            let away = position.subtract(&self.map.walls[index].shape.closest_point(&position));
+            let distance = away.magnitude();
+            if distance > f32::EPSILON && distance < player.collision_radius {
+                self.camera
+                    .translate(&away.multiply((player.collision_radius - distance) / distance));
+            }
+        }
+
+        let sector = self.current_sector();
+        let (floor, ceiling) = (sector.floor_height, sector.ceiling_height);
+
+        // Standing up is only possible with enough room above
+        let target = if self.crouching || self.feet + player.eye_height >= ceiling {
+            player.crouch_height
+        } else {
+            player.eye_height
+        };
+        let step = CROUCH_SPEED * delta;
+        self.height += (target - self.height).clamp(-step, step);
+
+        if !self.on_ground() || self.vertical_speed > 0.0 {
+            self.vertical_speed -= player.gravity * delta;
+            self.feet += self.vertical_speed * delta;
+        }
+        if self.feet + self.height > ceiling {
+            self.feet = ceiling - self.height;
+            self.vertical_speed = self.vertical_speed.min(0.0);
+        }
+        if self.feet <= floor {
+            self.feet = floor;
+            self.vertical_speed = 0.0;
+        }
+        self.camera.eye_height = self.feet + self.height;
+    }
+
+    fn on_ground(&self) -> bool {
+        self.feet <= self.current_sector().floor_height + f32::EPSILON
+    }
+
+    fn step(&self, direction: &Direction, speed: f32) -> Vector2 {
+        match direction {
             Direction::Forward => self.camera.forward().multiply(speed),
             Direction::Backward => self.camera.backward().multiply(speed),
             Direction::Left => self.camera.left().multiply(speed),
             Direction::Right => self.camera.right().multiply(speed),
-        };
-        if let Some(hit) = self.map.cast(&Ray::new(self.camera.pos, direction)) {
-            hit.intersec.distance > self.config.player.collision_radius
-        } else {
-            true
         }
     }
 
-    pub fn get_frame(&self) -> Vec<((i32, i32), Color)> {
-        self.camera
+    // Moves the player, coming out of the target of the first portal crossed
+    // on the way turned and raised like the portal's link
+    fn walk(&mut self, step: &Vector2) {
+        let portal = self
+            .map
+            .cast_all(&Ray::new(self.camera.pos, *step))
+            .into_iter()
+            .take_while(|hit| hit.intersec.distance <= step.magnitude())
+            .find_map(|hit| hit.wall.portal);
+        match portal {
+            Some(portal) => {
+                let exit = portal.point(&self.camera.pos.add(step));
+                self.camera.translate(&exit.subtract(&self.camera.pos));
+                self.camera.rotate(portal.angle.to_degrees());
+                self.feet += portal.elevation;
+            }
+            None => self.camera.translate(step),
+        }
+    }
+
+    // TODO Can do better
+    fn can_move(&self, direction: &Direction, speed: f32) -> bool {
+        let step = self.step(direction, speed);
+        let radius = self.config.player.collision_radius;
+        // Walls can only be crossed through openings that are neither too
+        // high to step on nor too low to fit under, and portals always, what
+        // lies behind them not being where the player ends up. Doors stop the
+        // player wherever they currently are
+        let passable = |wall: &Wall| {
+            wall.portal.is_some()
+                || wall.opening.is_some_and(|opening| {
+                    opening.bottom - self.feet <= MAX_STEP_HEIGHT
+                        && opening.top - opening.bottom.max(self.feet) >= self.height
+                })
+        };
+        let crossed = self
+            .map
+            .cast_all(&Ray::new(self.camera.pos, step))
+            .iter()
+            .take_while(|hit| hit.intersec.distance <= radius)
+            .take_while(|hit| hit.wall.portal.is_none())
+            .all(|hit| passable(hit.wall));
+
+        // The ray only looks straight ahead, walls such as pillars can still
+        // get too close on the side of the way
+        let destination = self.camera.pos.add(&step);
+        crossed
+            && self
+                .map
+                .walls
+                .iter()
+                .filter(|wall| !passable(wall))
+                .all(|wall| {
+                    let distance = |point: &Vector2| {
+                        wall.shape.closest_point(point).subtract(point).magnitude()
+                    };
+                    distance(&destination) + COLLISION_TOLERANCE
+                        >= radius.min(distance(&self.camera.pos))
+                })
+    }
+
+    fn current_sector(&self) -> &Sector {
+        self.map
+            .sector_at(&self.camera.pos)
+            .unwrap_or(&self.outdoors)
+    }
+
+    pub fn render(&self, frame: &mut Framebuffer) {
+        let height = frame.height;
+        let horizon = height as f32 / 2.0 + self.camera.horizon_offset();
+        let sector = self.current_sector();
+        let eye = self.camera.eye_height;
+        let mut columns = self
+            .camera
             .compute_frame(&self.map)
             .into_par_iter()
+            .zip(self.camera.rays())
+            .map(|(hits, ray)| {
+                let view = ColumnView {
+                    ray,
+                    eye,
+                    horizon,
+                    depth: 0.0,
+                    reflections: 0,
+                    portals: 0,
+                };
+                let mut column = Column::new(height as usize);
+                self.trace(&mut column, &view, sector, hits);
+                column
+            })
+            .collect::<Vec<Column>>();
+
+        // Draw the sprites back to front over the walls, clipped by the depth buffer
+        let sprites = self.project_sprites(eye, horizon);
+        columns.par_iter_mut().enumerate().for_each(|(x, column)| {
+            sprites
+                .iter()
+                .for_each(|sprite| self.draw_sprite_column(sprite, x, column))
+        });
+
+        let columns = columns
+            .into_par_iter()
+            .map(Column::composite)
+            .collect::<Vec<Vec<Color>>>();
+        let stride = frame.stride();
+        frame
+            .pixels
+            .par_chunks_exact_mut(stride)
             .enumerate()
-            .filter_map(|(x, hit)| hit.map(|hit| (x, hit)))
-            .filter(|(_, hit)| {
-                hit.intersec.distance > f32::EPSILON
-                    && hit.intersec.distance < self.config.screen.height as f32
+            .for_each(|(y, row)| {
+                row.chunks_exact_mut(4)
+                    .zip(&columns)
+                    .for_each(|(pixel, column)| pixel.copy_from_slice(&column[y].to_rgba()))
+            });
+    }
+
+    // Draws what the ray hits into the uncovered pixels of the column
+    fn trace<'a>(
+        &'a self,
+        column: &mut Column,
+        view: &ColumnView,
+        mut sector: &'a Sector,
+        hits: Vec<Hit>,
+    ) {
+        let height = column.colors.len() as u32;
+        let mut near = view.depth;
+
+        // Draw front to back, everything only filling the pixels left
+        // uncovered by what is in front of it
+        for hit in hits {
+            if column.uncovered == 0 {
+                break;
+            }
+            let distance = view.distance(&hit);
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            // The floor and the ceiling of the sector the ray went through
+            self.draw_planes(column, view, sector, near, distance);
+            near = distance;
+
+            let scale = self.camera.projection_distance() / distance;
+            let top = view.horizon - (hit.wall.top - view.eye) * scale;
+            let bottom = view.horizon - (hit.wall.bottom - view.eye) * scale;
+            let start = top.round().clamp(0.0, height as f32) as usize;
+            let end = bottom.round().clamp(0.0, height as f32) as usize;
+            let see_through = match (&hit.wall.mirror, &hit.wall.portal) {
+                (Some(_), _) => view.reflections < self.config.max_reflections,
+                (None, Some(_)) => view.portals < self.config.max_portals,
+                (None, None) => false,
+            };
+            if see_through {
+                self.see_through(column, view, sector, &hit, start..end);
+                continue;
+            }
+
+            let texture = self.texture(&hit.wall.texture_name);
+            let mapping = &hit.wall.mapping;
+            let along = hit.intersec.offset * hit.wall.length;
+            let tint = [mapping.tint.r, mapping.tint.g, mapping.tint.b].map(|c| c as f32 / 255.0);
+            let point = hit.intersec.position;
+            let occluders = self
+                .lights
+                .iter()
+                .map(|light| match &hit.wall.lightmap {
+                    Some(_) if light.is_static => Vec::new(),
+                    _ => light.occluders(&self.map, &point),
+                })
+                .collect::<Vec<Vec<Hit>>>();
+            for y in start..end {
+                if column.colors[y].is_some() {
+                    continue;
+                }
+                let elevation = view.eye + (view.horizon - y as f32 - 0.5) / scale;
+                if let Some(opening) = &hit.wall.opening {
+                    if (opening.bottom..opening.top).contains(&elevation) {
+                        continue;
+                    }
+                }
+                let (u, v) = mapping.uv(along, hit.wall.top - elevation);
+                let color = self.light_wall(
+                    self.sample(texture, u, v, distance, mapping.scale)
+                        .scale(tint),
+                    &hit,
+                    elevation,
+                    &occluders,
+                );
+                let color = self.apply_fog(color, distance);
+                match hit.wall.blend {
+                    BlendMode::AlphaTest if color.a < ALPHA_CUTOFF => {}
+                    BlendMode::AlphaBlend if color.a < u8::MAX => {
+                        if color.a > 0 {
+                            column.overlays[y].push((distance, color));
+                        }
+                    }
+                    _ => column.fill(
+                        y,
+                        Color {
+                            a: u8::MAX,
+                            ..color
+                        },
+                        distance,
+                    ),
+                }
+            }
+
+            if let Some(opening) = &hit.wall.opening {
+                sector = &self.map.sectors[opening.entered_sector(hit.intersec.side)];
+            }
+        }
+        self.draw_planes(column, view, sector, near, f32::INFINITY);
+    }
+
+    // Fills the uncovered pixels of a mirror or a portal between the `rows`
+    // with what the ray sees once reflected or sent through, seen as lying at
+    // the wall's distance
+    fn see_through(
+        &self,
+        column: &mut Column,
+        view: &ColumnView,
+        sector: &Sector,
+        hit: &Hit,
+        rows: Range<usize>,
+    ) {
+        let distance = view.distance(hit);
+        let scale = self.camera.projection_distance() / distance;
+        // Whether each row of the column shows what is seen through the wall
+        let seen_rows = (0..column.colors.len())
+            .map(|y| {
+                let elevation = view.eye + (view.horizon - y as f32 - 0.5) / scale;
+                rows.contains(&y)
+                    && column.colors[y].is_none()
+                    && !hit
+                        .wall
+                        .opening
+                        .is_some_and(|opening| (opening.bottom..opening.top).contains(&elevation))
             })
-            .map(|(x, hit)| {
-                let wall_height = self.config.screen.height as f32 / hit.intersec.distance;
-                let wall_top = (self.config.screen.height as f32 - wall_height) / 2.0;
-                let texture = self.textures
-                    .get(&hit.wall.texture_name)
-                    .unwrap_or(&self.default_texture);
-                let texture_length = hit.wall.length / hit.wall.repeat_texture as f32;
-                let offset_in_wall = hit.intersec.offset * hit.wall.length;
-                let texture_offset = (offset_in_wall % texture_length) / texture_length;
+            .collect::<Vec<bool>>();
+        if !seen_rows.contains(&true) {
+            return;
+        }
+
+        let direction = view.ray.direction;
+        let (ray, eye, sector, exit) = match (&hit.wall.mirror, &hit.wall.portal) {
+            (Some(_), _) => {
+                let normal = hit.intersec.normal;
+                let ray = Ray::new(
+                    hit.intersec.position,
+                    direction.subtract(&normal.multiply(2.0 * direction.dot(&normal))),
+                );
+                (ray, view.eye, sector, hit.wall)
+            }
+            (None, Some(portal)) => {
+                let ray = Ray::new(
+                    portal.point(&hit.intersec.position),
+                    portal.direction(&direction),
+                );
+                // The sector the ray comes out in, just past the target wall
+                let sector = self
+                    .map
+                    .sector_at(&ray.origin.add(&ray.direction.multiply(1e-3)))
+                    .unwrap_or(&self.outdoors);
+                let target = &self.map.walls[portal.target];
+                (ray, view.eye + portal.elevation, sector, target)
+            }
+            (None, None) => return,
+        };
+
+        // Only the pixels of the wall are left to draw in what is seen through it
+        let mut seen = Column::new(column.colors.len());
+        (0..column.colors.len())
+            .filter(|&y| !seen_rows[y])
+            .for_each(|y| seen.fill(y, Color::BLANK, 0.0));
+
+        let hits = self
+            .map
+            .cast_all(&ray)
+            .into_iter()
+            .filter(|other| !std::ptr::eq(other.wall, exit))
+            .collect();
+        let mirrored = hit.wall.mirror.is_some() as u32;
+        let seen_view = ColumnView {
+            ray: &ray,
+            eye,
+            depth: distance,
+            reflections: view.reflections + mirrored,
+            portals: view.portals + 1 - mirrored,
+            ..*view
+        };
+        self.trace(&mut seen, &seen_view, sector, hits);
+
+        for y in rows.filter(|&y| seen_rows[y]) {
+            let color = seen.composite_pixel(y);
+            let color = match &hit.wall.mirror {
+                Some(mirror) => mirror.reflect(color),
+                None => color,
+            };
+            column.fill(y, color, distance);
+        }
+    }
+
+    // Fills the uncovered pixels showing the floor and the ceiling of the
+    // sector between the `near` and `far` distances
+    fn draw_planes(
+        &self,
+        column: &mut Column,
+        view: &ColumnView,
+        sector: &Sector,
+        near: f32,
+        far: f32,
+    ) {
+        for y in 0..column.colors.len() {
+            if column.colors[y].is_some() {
+                continue;
+            }
+            let row = y as f32 + 0.5 - view.horizon;
+            let (surface, default_color, height) = if row < 0.0 {
                 (
-                    (x as i32, wall_top as i32),
-                    texture.get_line(
-                        (texture_offset * 255.) as u8,
-                        wall_height as u32,
-                    )
+                    sector.ceiling.as_ref(),
+                    CEILING_COLOR,
+                    sector.ceiling_height - view.eye,
                 )
+            } else {
+                (
+                    sector.floor.as_ref(),
+                    FLOOR_COLOR,
+                    view.eye - sector.floor_height,
+                )
+            };
+            let distance = height * self.camera.projection_distance() / row.abs();
+            if !(near..far).contains(&distance) {
+                continue;
+            }
+
+            match (&self.sky, surface) {
+                (Some(sky), None) if row < 0.0 => {
+                    let angle = view
+                        .ray
+                        .direction
+                        .y
+                        .atan2(view.ray.direction.x)
+                        .to_degrees();
+                    let elevation = -row / (column.colors.len() as f32 / 2.0);
+                    let color = sky.color(angle, elevation, |name| self.texture(name));
+                    column.fill(y, color, f32::INFINITY);
+                }
+                _ => column.fill(
+                    y,
+                    self.apply_fog(
+                        self.surface_color(surface, default_color, view, distance),
+                        distance,
+                    ),
+                    distance,
+                ),
+            }
+        }
+    }
+
+    // Sprites in front of the camera, the farthest first
+    fn project_sprites(&self, eye: f32, horizon: f32) -> Vec<ProjectedSprite<'_>> {
+        let mut sprites = self
+            .sprites
+            .iter()
+            .filter_map(|sprite| {
+                let (x, depth) = self.camera.project(&sprite.position)?;
+                let texture = self.texture(&sprite.texture_name);
+                let scale = self.camera.projection_distance() / depth;
+                let height = sprite.scale * scale;
+                let width = height * texture.width as f32 / texture.height as f32;
+                let sector = self
+                    .map
+                    .sector_at(&sprite.position)
+                    .unwrap_or(&self.outdoors);
+                let top = match sprite.anchor {
+                    SpriteAnchor::Floor => sector.floor_height + sprite.scale,
+                    SpriteAnchor::Center => {
+                        (sector.floor_height + sector.ceiling_height + sprite.scale) / 2.0
+                    }
+                    SpriteAnchor::Ceiling => sector.ceiling_height,
+                };
+                Some(ProjectedSprite {
+                    texture,
+                    depth,
+                    left: x - width / 2.0,
+                    top: horizon - (top - eye) * scale,
+                    width,
+                    height,
+                })
             })
-            .flat_map(|(pos, line)| {
-                line.into_par_iter()
-                    .enumerate()
-                    .map(move |(i, color)| ((pos.0, pos.1 + i as i32), color))
+            .collect::<Vec<ProjectedSprite>>();
+        sprites.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+        sprites
+    }
+
+    fn draw_sprite_column(&self, sprite: &ProjectedSprite, x: usize, column: &mut Column) {
+        let u = (x as f32 + 0.5 - sprite.left) / sprite.width;
+        if !(0.0..1.0).contains(&u) {
+            return;
+        }
+        let height = column.colors.len() as f32;
+        let start = sprite.top.round().clamp(0.0, height) as usize;
+        let end = (sprite.top + sprite.height).round().clamp(0.0, height) as usize;
+        for y in start..end {
+            if sprite.depth >= column.depths[y] {
+                continue;
+            }
+            let v = (y as f32 + 0.5 - sprite.top) / sprite.height;
+            let color = self.apply_fog(sprite.texture.get_pixel(u, v), sprite.depth);
+            match color.a {
+                0 => {}
+                u8::MAX => {
+                    column.colors[y] = Some(color);
+                    column.depths[y] = sprite.depth;
+                }
+                _ => {
+                    column.colors[y] = column.colors[y].map(|behind| behind.blend(&color));
+                }
+            }
+        }
+    }
+
+    // Walls are lit by the ambient light, the static lights once baked into
+    // the lightmap and the other lights
+    fn light_wall(&self, color: Color, hit: &Hit, elevation: f32, occluders: &[Vec<Hit>]) -> Color {
+        let wall = hit.wall;
+        let Intersection {
+            position,
+            offset,
+            normal,
+            side,
+            ..
+        } = &hit.intersec;
+        let ambient = self.shading.factor(normal);
+        if self.lights.is_empty() && wall.lightmap.is_none() {
+            return color.scale([ambient; 3]);
+        }
+        let baked = wall.lightmap.as_ref().map(|lightmap| {
+            let v = (elevation - wall.bottom) / (wall.top - wall.bottom).max(f32::EPSILON);
+            lightmap.sample(*side == Side::Left, *offset, v)
+        });
+        let light = self
+            .lights
+            .iter()
+            .zip(occluders)
+            .filter(|(light, _)| !(light.is_static && baked.is_some()))
+            .filter(|(light, occluders)| !light.is_occluded(occluders, position, elevation))
+            .map(|(light, _)| light.illuminance(position, elevation, normal))
+            .chain(baked)
+            .fold([ambient; 3], |[r, g, b], [lr, lg, lb]| {
+                [r + lr, g + lg, b + lb]
+            });
+        color.scale(light)
+    }
+
+    fn texture(&self, name: &str) -> &Texture {
+        self.textures
+            .get(name)
+            .map_or(&self.default_texture, |animation| {
+                animation.frame(self.time)
             })
-            .collect()
+    }
+
+    // Samples the surface where the ray meets it, the ray's direction having
+    // a forward component of one so that it scales with the perpendicular distance
+    fn surface_color(
+        &self,
+        surface: Option<&Surface>,
+        default_color: Color,
+        view: &ColumnView,
+        distance: f32,
+    ) -> Color {
+        let Some(surface) = surface else {
+            return default_color;
+        };
+        let ray = view.ray;
+        let point = ray
+            .origin
+            .add(&ray.direction.multiply(distance - view.depth));
+        let mapping = surface.mapping_at(&point);
+        let (u, v) = mapping.uv(&point);
+        self.sample(
+            self.texture(&mapping.texture_name),
+            u,
+            v,
+            distance,
+            mapping.scale.abs(),
+        )
+    }
+
+    // Samples a texture covering `size` world units seen from `distance`,
+    // using the mip level closest to one texel per pixel
+    fn sample(&self, texture: &Texture, u: f32, v: f32, distance: f32, size: f32) -> Color {
+        let texture = if self.config.mipmaps {
+            let texels_per_pixel =
+                texture.height as f32 * distance / (self.camera.projection_distance() * size);
+            texture.mip(texels_per_pixel)
+        } else {
+            texture
+        };
+        texture.sample(u, v, self.config.filter)
+    }
+
+    fn apply_fog(&self, color: Color, distance: f32) -> Color {
+        match &self.fog {
+            Some(fog) => fog.apply(color, distance),
+            None => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use super::*;
+    use crate::{
+        door::DoorState,
+        math::{
+            assert_close,
+            geometry::{Bounds, Castable},
+        },
+    };
+
+    // Scene around the origin, `fields` holding the map and the rest
+    fn scene_with(fields: &str) -> Scene {
+        scene_with_textures("{}", fields, Vec::new())
+    }
+
+    fn scene_with_textures(textures: &str, fields: &str, walls: Vec<Wall>) -> Scene {
+        let config = serde_json::from_str(include_str!("../assets/config.json")).unwrap();
+        let scene_config = serde_json::from_str(&format!(
+            r#"{{
+                "camera": {{ "position": {{ "x": 0, "y": 0 }}, "angle": 0 }},
+                "textures": {textures},
+                {fields}
+            }}"#
+        ))
+        .unwrap();
+        Scene::with_walls(&config, scene_config, walls)
+    }
+
+    // PNG for a scene to load, deleted once dropped, its path showing when
+    // formatted
+    struct Png(PathBuf);
+
+    impl std::fmt::Display for Png {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0.display())
+        }
+    }
+
+    impl Drop for Png {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    // Saves the pixels, rows of `width` of them, under a name no other test
+    // or test run uses at the same time
+    fn png(name: &str, width: u32, pixels: &[[u8; 4]]) -> Png {
+        let path = std::env::temp_dir().join(format!("rayna-{}-{name}.png", std::process::id()));
+        let height = pixels.len() as u32 / width;
+        image::save_buffer(
+            &path,
+            &pixels.concat(),
+            width,
+            height,
+            image::ColorType::Rgba8,
+        )
+        .unwrap();
+        Png(path)
+    }
+
+    // Color of the pixel at the center of the frame
+    fn center_pixel(scene: &Scene) -> [u8; 3] {
+        let mut frame = Framebuffer::new(640, 480);
+        scene.render(&mut frame);
+        let i = 240 * frame.stride() + 320 * 4;
+        [frame.pixels[i], frame.pixels[i + 1], frame.pixels[i + 2]]
+    }
+
+    fn scene() -> Scene {
+        scene_with(r#""map": {}"#)
+    }
+
+    #[test]
+    fn test_jump() {
+        let mut scene = scene();
+        assert_eq!(scene.camera.eye_height, 0.5);
+
+        scene.handle_inputs(&[Command::Jump]);
+        scene.update(0.1);
+        assert!(scene.camera.eye_height > 0.5);
+
+        // Jumping again is not possible in the air
+        let speed = scene.vertical_speed;
+        scene.handle_inputs(&[Command::Jump]);
+        assert_eq!(scene.vertical_speed, speed);
+
+        // Falls back down and lands on the floor
+        (0..100).for_each(|_| scene.update(0.1));
+        assert_eq!(scene.camera.eye_height, 0.5);
+        assert!(scene.on_ground());
+    }
+
+    #[test]
+    fn test_crouch() {
+        let mut scene = scene();
+        scene.handle_inputs(&[Command::Crouch]);
+        scene.update(0.05);
+        assert!(scene.camera.eye_height < 0.5);
+        scene.update(1.0);
+        assert_eq!(scene.camera.eye_height, 0.25);
+
+        // Stands back up once the command stops
+        scene.handle_inputs(&[]);
+        scene.update(1.0);
+        assert_eq!(scene.camera.eye_height, 0.5);
+    }
+
+    #[test]
+    fn test_collision() {
+        // Pillar beside the way, out of the way of rays going forward
+        let mut scene = scene_with(
+            r#""map": {
+                "walls": [{
+                    "texture": "wall",
+                    "circle": { "center": { "x": 2, "y": 1.2 }, "radius": 0.5 }
+                }]
+            }"#,
+        );
+        scene.handle_inputs(&[Command::Move(Direction::Forward, 1.0)]);
+        assert_eq!(scene.camera.pos, Vector2::new(1.0, 0.0));
+        scene.handle_inputs(&[Command::Move(Direction::Forward, 1.0)]);
+        assert_eq!(scene.camera.pos, Vector2::new(1.0, 0.0));
+        scene.handle_inputs(&[Command::Move(Direction::Backward, 1.0)]);
+        assert!(scene.camera.pos.magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_pushed() {
+        // Barrier sweeping towards the player along the x axis
+        let mut scene = scene_with(
+            r#""map": {
+                "walls": [{
+                    "id": "barrier",
+                    "texture": "wall",
+                    "line": { "start": { "x": 3, "y": -1 }, "end": { "x": 3, "y": 1 } }
+                }]
+            },
+            "movers": [{
+                "walls": ["barrier"],
+                "keyframes": [
+                    { "time": 0 },
+                    { "time": 1, "offset": { "x": -3, "y": 0 } }
+                ],
+                "loop": "once"
+            }]"#,
+        );
+        (0..5).for_each(|_| scene.update(0.1));
+        assert_eq!(scene.camera.pos, Vector2::new(0.0, 0.0));
+        (0..5).for_each(|_| scene.update(0.1));
+        assert!((scene.camera.pos.x + 1.0).abs() < 1e-5);
+        let hits = scene
+            .map
+            .cast_all(&Ray::new(scene.camera.pos, Vector2::new(1.0, 0.0)));
+        assert!((hits[0].intersec.distance - 1.0).abs() < 1e-5);
+    }
+
+    fn door_wall(id: &str, x: f32) -> String {
+        format!(
+            r#"{{
+                "id": "{id}",
+                "texture": "wall",
+                "line": {{ "start": {{ "x": {x}, "y": 1 }}, "end": {{ "x": {x}, "y": -1 }} }}
+            }}"#
+        )
+    }
+
+    #[test]
+    fn test_use() {
+        // Only the nearest door in front of the player within reach opens
+        let mut scene = scene_with(&format!(
+            r#""map": {{ "walls": [{}, {}, {}] }},
+            "doors": [
+                {{ "wall": "behind", "slide": {{}} }},
+                {{ "wall": "next", "slide": {{}} }},
+                {{ "wall": "near", "slide": {{}} }}
+            ]"#,
+            door_wall("behind", -1.2),
+            door_wall("next", 1.4),
+            door_wall("near", 1.2),
+        ));
+        let states = |scene: &Scene| {
+            scene
+                .doors
+                .iter()
+                .map(|door| door.state)
+                .collect::<Vec<_>>()
+        };
+        scene.handle_inputs(&[Command::Use]);
+        assert_eq!(
+            states(&scene),
+            [DoorState::Closed, DoorState::Closed, DoorState::Opening]
+        );
+        scene.handle_inputs(&[Command::Look(180.0), Command::Use]);
+        assert_eq!(
+            states(&scene),
+            [DoorState::Opening, DoorState::Closed, DoorState::Opening]
+        );
+
+        let mut scene = scene_with(&format!(
+            r#""map": {{ "walls": [{}] }},
+            "doors": [{{ "wall": "far", "slide": {{}} }}]"#,
+            door_wall("far", 2.0),
+        ));
+        scene.handle_inputs(&[Command::Use]);
+        assert_eq!(scene.doors[0].state, DoorState::Closed);
+    }
+
+    #[test]
+    fn test_door_in_the_way() {
+        // A door swinging open towards the player stops, or pushes them aside
+        let scene = |blocking: bool| {
+            let mut scene = scene_with(&format!(
+                r#""map": {{ "walls": [{}] }},
+                "doors": [{{ "wall": "door", "swing": {{ "angle": -90 }}, "blocking": {blocking} }}]"#,
+                door_wall("door", 1.5),
+            ));
+            scene.handle_inputs(&[Command::Use]);
+            (0..40).for_each(|_| scene.update(0.05));
+            scene
+        };
+        let distance = |scene: &Scene| {
+            let position = scene.camera.pos;
+            let wall = &scene.map.walls[0];
+            wall.shape
+                .closest_point(&position)
+                .subtract(&position)
+                .magnitude()
+        };
+
+        let blocked = scene(true);
+        assert_eq!(blocked.doors[0].state, DoorState::Opening);
+        assert_eq!(blocked.camera.pos, Vector2::new(0.0, 0.0));
+        assert!(distance(&blocked) + COLLISION_TOLERANCE >= 1.0);
+
+        let pushing = scene(false);
+        assert_eq!(pushing.doors[0].state, DoorState::Open);
+        assert!(pushing.camera.pos != Vector2::new(0.0, 0.0));
+        assert!(distance(&pushing) + COLLISION_TOLERANCE >= 1.0);
+    }
+
+    #[test]
+    fn test_moved_once() {
+        // The mover is left out as the door already moves the wall
+        let scene = scene_with(
+            r#""map": {
+                "walls": [{
+                    "id": "gate",
+                    "texture": "wall",
+                    "line": { "start": { "x": 3, "y": -1 }, "end": { "x": 3, "y": 1 } }
+                }]
+            },
+            "doors": [{ "wall": "gate", "slide": {} }],
+            "movers": [{
+                "walls": ["gate"],
+                "keyframes": [{ "time": 0, "offset": { "x": 1, "y": 0 } }]
+            }]"#,
+        );
+        assert_eq!(scene.doors.len(), 1);
+        assert!(scene.movers.is_empty());
+        assert_eq!(scene.map.walls[0].shape.point(0.0), Vector2::new(3.0, -1.0));
+    }
+
+    #[test]
+    fn test_walk_through_portal() {
+        // The exit faces north half a unit up, a quarter turn from the entrance
+        let mut scene = scene_with(
+            r#""map": {
+                "walls": [
+                    {
+                        "portal": "out",
+                        "texture": "wall",
+                        "line": { "start": { "x": 1, "y": -1 }, "end": { "x": 1, "y": 1 } }
+                    },
+                    {
+                        "id": "out",
+                        "texture": "wall",
+                        "bottom": 0.5,
+                        "top": 1.5,
+                        "line": { "start": { "x": 10, "y": 5 }, "end": { "x": 12, "y": 5 } }
+                    }
+                ]
+            }"#,
+        );
+        scene.handle_inputs(&[Command::Move(Direction::Forward, 1.5)]);
+        assert_close(scene.camera.pos, Vector2::new(11.0, 5.5));
+        assert_close(scene.camera.forward(), Vector2::new(0.0, 1.0));
+        assert_eq!(scene.feet, 0.5);
+    }
+
+    #[test]
+    fn test_mirror_through_portal() {
+        // A green wall that reflects in red, seen through a portal
+        let mut scene = scene_with(
+            r##""map": {
+                "walls": [
+                    {
+                        "id": "in",
+                        "portal": "out",
+                        "texture": "wall",
+                        "line": { "start": { "x": 2, "y": -1 }, "end": { "x": 2, "y": 1 } }
+                    },
+                    {
+                        "id": "out",
+                        "portal": "in",
+                        "texture": "wall",
+                        "line": { "start": { "x": 10, "y": 1 }, "end": { "x": 10, "y": -1 } }
+                    },
+                    {
+                        "texture": "wall",
+                        "tint": "#00ff00",
+                        "mirror": { "tint": "#ff0000" },
+                        "line": { "start": { "x": 13, "y": -5 }, "end": { "x": 13, "y": 5 } }
+                    }
+                ]
+            }"##,
+        );
+        let center_column = |scene: &Scene| {
+            let mut frame = Framebuffer::new(640, 480);
+            scene.render(&mut frame);
+            (200..280)
+                .map(|y| {
+                    let i = y * frame.stride() + 320 * 4;
+                    [frame.pixels[i], frame.pixels[i + 1], frame.pixels[i + 2]]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Crossing a portal does not use up the only reflection allowed
+        scene.config.max_reflections = 1;
+        let pixels = center_column(&scene);
+        assert!(pixels.iter().all(|[_, g, b]| *g == 0 && *b == 0));
+        assert!(pixels.iter().any(|[r, _, _]| *r > 0));
+
+        scene.config.max_reflections = 0;
+        let pixels = center_column(&scene);
+        assert!(pixels.iter().all(|[r, _, b]| *r == 0 && *b == 0));
+        assert!(pixels.iter().any(|[_, g, _]| *g > 0));
+    }
+
+    #[test]
+    fn test_blend() {
+        // A wall in front of a blue one, its texture being a single texel
+        let center_through = |name: &str, texel: [u8; 4], blend: &str| {
+            let front = png(name, 1, &[texel]);
+            let back = png(&format!("{name}-back"), 1, &[[0, 0, 255, 255]]);
+            let textures = format!(r#"{{ "front": "{front}", "back": "{back}" }}"#);
+            center_pixel(&scene_with_textures(
+                &textures,
+                &format!(
+                    r#""map": {{
+                        "walls": [
+                            {{
+                                "texture": "front",
+                                "blend": "{blend}",
+                                "line": {{ "start": {{ "x": 2, "y": 2 }}, "end": {{ "x": 2, "y": -2 }} }}
+                            }},
+                            {{
+                                "texture": "back",
+                                "line": {{ "start": {{ "x": 4, "y": 2 }}, "end": {{ "x": 4, "y": -2 }} }}
+                            }}
+                        ]
+                    }}"#
+                ),
+                Vec::new(),
+            ))
+        };
+
+        assert_eq!(
+            center_through("test-clear", [255, 0, 0, 0], "alphaTest"),
+            [0, 0, 255]
+        );
+        assert_eq!(
+            center_through("test-solid", [255, 0, 0, 255], "alphaTest"),
+            [255, 0, 0]
+        );
+        assert_eq!(
+            center_through("opaque", [255, 0, 0, 0], "opaque"),
+            [255, 0, 0]
+        );
+        let [r, g, b] = center_through("blend", [255, 0, 0, 128], "alphaBlend");
+        assert!(
+            r.abs_diff(128) <= 1 && g == 0 && b.abs_diff(127) <= 1,
+            "{r} {g} {b}"
+        );
+    }
+
+    #[test]
+    fn test_atlas_region() {
+        // Red and green halves, the wall only showing the green one
+        let atlas = png(
+            "atlas",
+            2,
+            &[
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+            ],
+        );
+        let center_with = |texture: String| {
+            center_pixel(&scene_with_textures(
+                &format!(r#"{{ "green": {texture} }}"#),
+                r#""map": {
+                    "walls": [{
+                        "texture": "green",
+                        "line": { "start": { "x": 2, "y": 2 }, "end": { "x": 2, "y": -2 } }
+                    }]
+                }"#,
+                Vec::new(),
+            ))
+        };
+        let region =
+            |rect: &str| center_with(format!(r#"{{ "atlas": "{atlas}", "rect": {rect} }}"#));
+        assert_eq!(region("[1, 0, 1, 2]"), [0, 255, 0]);
+
+        // Regions out of the atlas show like missing textures
+        let missing = center_with(r#""missing.png""#.into());
+        assert_eq!(region("[1, 0, 2, 2]"), missing);
+        assert_eq!(region("[0, 0, 0, 2]"), missing);
+    }
+
+    // Wall across the x axis from y = 1 down to y = -1, shaped by hand
+    #[derive(Debug)]
+    struct Gate {
+        x: f32,
+    }
+
+    impl Castable for Gate {
+        fn cast(&self, ray: &Ray) -> Option<Intersection> {
+            let t = (self.x - ray.origin.x) / ray.direction.x;
+            let position = ray.origin.add(&ray.direction.multiply(t));
+            let (normal, side) = if ray.direction.x > 0.0 {
+                (Vector2::new(-1.0, 0.0), Side::Right)
+            } else {
+                (Vector2::new(1.0, 0.0), Side::Left)
+            };
+            (t >= 0.0 && position.y.abs() <= 1.0).then_some(Intersection {
+                distance: t * ray.direction.magnitude(),
+                position,
+                offset: (1.0 - position.y) / 2.0,
+                normal,
+                side,
+            })
+        }
+
+        fn bounds(&self) -> Bounds {
+            Bounds::around([Vector2::new(self.x, -1.0), Vector2::new(self.x, 1.0)])
+        }
+
+        fn closest_point(&self, point: &Vector2) -> Vector2 {
+            Vector2::new(self.x, point.y.clamp(-1.0, 1.0))
+        }
+
+        fn normal(&self, _offset: f32) -> Vector2 {
+            Vector2::new(1.0, 0.0)
+        }
+
+        fn length(&self) -> f32 {
+            2.0
+        }
+
+        fn point(&self, offset: f32) -> Vector2 {
+            Vector2::new(self.x, 1.0 - 2.0 * offset)
+        }
+    }
+
+    #[test]
+    fn test_custom_shape() {
+        let texture = png("gate", 1, &[[0, 255, 0, 255]]);
+        let gate = Wall::new(Arc::new(Gate { x: 2.0 }), "gate".into(), 0.0, 1.0);
+        let scene = scene_with_textures(
+            &format!(r#"{{ "gate": "{texture}" }}"#),
+            r#""map": {}"#,
+            vec![gate],
+        );
+        assert_eq!(center_pixel(&scene), [0, 255, 0]);
     }
 }
@@ -0,0 +1,97 @@
+use crate::{
+    color::Color,
+    config::{SkyConfig, SkyLayerConfig},
+    texture::Texture,
+};
+
+#[derive(Debug, Clone)]
+struct SkyLayer {
+    texture_name: String,
+    parallax: f32,
+    height: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sky {
+    texture_name: String,
+    layers: Vec<SkyLayer>,
+}
+
+impl Sky {
+    pub fn from_config(config: SkyConfig) -> Self {
+        Self {
+            texture_name: config.texture,
+            layers: config
+                .layers
+                .into_iter()
+                .map(
+                    |SkyLayerConfig {
+                         texture,
+                         parallax,
+                         height,
+                     }| SkyLayer {
+                        texture_name: texture,
+                        parallax,
+                        height: height.max(f32::EPSILON),
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    /// Color of the sky looking towards `angle_deg`, `elevation` going from
+    /// 0 on the horizon to 1 at the top of the sky
+    pub fn color<'a>(
+        &self,
+        angle_deg: f32,
+        elevation: f32,
+        texture: impl Fn(&str) -> &'a Texture,
+    ) -> Color {
+        // Angles grow counterclockwise, that is from right to left on screen
+        let u = |parallax: f32| (-angle_deg * parallax / 360.0).rem_euclid(1.0);
+        let v = |height: f32| (1.0 - elevation / height).clamp(0.0, 1.0);
+        self.layers
+            .iter()
+            .filter(|layer| elevation < layer.height)
+            .fold(
+                texture(&self.texture_name).get_pixel(u(1.0), v(1.0)),
+                |color, layer| {
+                    color.blend(
+                        &texture(&layer.texture_name).get_pixel(u(layer.parallax), v(layer.height)),
+                    )
+                },
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color() {
+        let sky = Sky::from_config(SkyConfig {
+            texture: "sky".into(),
+            layers: vec![SkyLayerConfig {
+                texture: "mountains".into(),
+                parallax: 2.0,
+                height: 0.5,
+            }],
+        });
+        let panorama = Texture::new(vec![Color::BLUE, Color::SKYBLUE], 2, 1);
+        let mountains = Texture::new(vec![Color::BLANK, Color::DARKGREEN], 2, 1);
+        let texture = |name: &str| match name {
+            "sky" => &panorama,
+            _ => &mountains,
+        };
+
+        // The panorama wraps around
+        assert_eq!(sky.color(-90.0, 0.75, texture), Color::BLUE);
+        assert_eq!(sky.color(-270.0, 0.75, texture), Color::SKYBLUE);
+        assert_eq!(sky.color(90.0, 0.75, texture), Color::SKYBLUE);
+
+        // The layer scrolls twice as fast and lets the panorama through
+        assert_eq!(sky.color(-45.0, 0.25, texture), Color::BLUE);
+        assert_eq!(sky.color(-90.0, 0.25, texture), Color::DARKGREEN);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{
+    config::{SpriteAnchor, SpriteConfig},
+    math::Vector2,
+};
+
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    pub position: Vector2,
+    pub texture_name: String,
+    pub scale: f32,
+    pub anchor: SpriteAnchor,
+}
+
+impl Sprite {
+    pub fn from_config(config: SpriteConfig) -> Self {
+        Self {
+            position: config.position,
+            texture_name: config.texture,
+            scale: config.scale.max(0.0),
+            anchor: config.anchor,
+        }
+    }
+}
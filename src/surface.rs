@@ -0,0 +1,114 @@
+use crate::{
+    config::{SurfaceConfig, SurfaceRegionConfig},
+    math::Vector2,
+};
+
+/// Texture placement on a floor or a ceiling
+#[derive(Debug, Clone)]
+pub struct SurfaceMapping {
+    pub texture_name: String,
+    /// Size in world units covered by the texture
+    pub scale: f32,
+    offset: Vector2,
+}
+
+#[derive(Debug, Clone)]
+struct Region {
+    min: Vector2,
+    max: Vector2,
+    mapping: SurfaceMapping,
+}
+
+#[derive(Debug, Clone)]
+pub struct Surface {
+    mapping: SurfaceMapping,
+    regions: Vec<Region>,
+}
+
+impl SurfaceMapping {
+    fn new(texture_name: String, scale: f32, offset: Vector2) -> Self {
+        Self {
+            texture_name,
+            scale: if scale.abs() > f32::EPSILON {
+                scale
+            } else {
+                1.0
+            },
+            offset,
+        }
+    }
+
+    /// Texture coordinates between 0 and 1 of a point in the world
+    pub fn uv(&self, point: &Vector2) -> (f32, f32) {
+        (
+            ((point.x + self.offset.x) / self.scale).rem_euclid(1.0),
+            ((point.y + self.offset.y) / self.scale).rem_euclid(1.0),
+        )
+    }
+}
+
+impl Surface {
+    pub fn from_config(config: SurfaceConfig) -> Self {
+        Self {
+            mapping: SurfaceMapping::new(config.texture, config.scale, config.offset),
+            regions: config
+                .regions
+                .into_iter()
+                .map(
+                    |SurfaceRegionConfig {
+                         min,
+                         max,
+                         texture,
+                         scale,
+                         offset,
+                     }| Region {
+                        min: Vector2::new(min.x.min(max.x), min.y.min(max.y)),
+                        max: Vector2::new(min.x.max(max.x), min.y.max(max.y)),
+                        mapping: SurfaceMapping::new(texture, scale, offset),
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    /// Mapping of the last region containing the point, or the surface's own
+    pub fn mapping_at(&self, point: &Vector2) -> &SurfaceMapping {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| {
+                (region.min.x..=region.max.x).contains(&point.x)
+                    && (region.min.y..=region.max.y).contains(&point.y)
+            })
+            .map_or(&self.mapping, |region| &region.mapping)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_at() {
+        let surface = Surface::from_config(SurfaceConfig {
+            texture: "floor".into(),
+            scale: 2.0,
+            offset: Vector2::new(0.5, 0.0),
+            regions: vec![SurfaceRegionConfig {
+                min: Vector2::new(4.0, 4.0),
+                max: Vector2::new(2.0, 2.0),
+                texture: "tiles".into(),
+                scale: 1.0,
+                offset: Vector2::default(),
+            }],
+        });
+
+        let mapping = surface.mapping_at(&Vector2::new(0.5, -1.0));
+        assert_eq!(mapping.texture_name, "floor");
+        assert_eq!(mapping.uv(&Vector2::new(0.5, -1.0)), (0.5, 0.5));
+
+        let mapping = surface.mapping_at(&Vector2::new(3.0, 3.25));
+        assert_eq!(mapping.texture_name, "tiles");
+        assert_eq!(mapping.uv(&Vector2::new(3.0, 3.25)), (0.0, 0.25));
+    }
+}
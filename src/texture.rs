@@ -1,25 +1,42 @@
 use std::path::Path;
 
-use raylib::color::Color;
+use crate::{color::Color, config::TextureFilter};
 
 #[derive(Debug, Clone)]
 pub struct Texture {
     pub pixels: Vec<Color>,
     pub width: u32,
     pub height: u32,
+    // Copies of the texture halved again and again, down to a single pixel
+    mips: Vec<Texture>,
 }
 
 impl Default for Texture {
     fn default() -> Self {
-        Self {
-            pixels: vec![Color::VIOLET, Color::BLACK, Color::BLACK, Color::VIOLET],
-            width: 2,
-            height: 2,
-        }
+        Self::new(
+            vec![Color::VIOLET, Color::BLACK, Color::BLACK, Color::VIOLET],
+            2,
+            2,
+        )
     }
 }
 
 impl Texture {
+    pub fn new(pixels: Vec<Color>, width: u32, height: u32) -> Self {
+        let mut texture = Self {
+            pixels,
+            width,
+            height,
+            mips: Vec::new(),
+        };
+        let mut level = texture.downsample();
+        while let Some(mip) = level {
+            level = mip.downsample();
+            texture.mips.push(mip);
+        }
+        texture
+    }
+
     pub fn from_png(path: &Path) -> Result<Self, ()> {
         let image = image::open(path).map_err(|_| ())?.to_rgba8();
         let (width, height) = image.dimensions();
@@ -28,23 +45,159 @@ impl Texture {
             .chunks(4)
             .map(|chunk| Color::new(chunk[0], chunk[1], chunk[2], chunk[3]))
             .collect::<Vec<Color>>();
-        Ok(Self {
+        Ok(Self::new(pixels, width, height))
+    }
+
+    // Half size copy averaging blocks of 2x2 pixels, if the texture can shrink
+    fn downsample(&self) -> Option<Self> {
+        if self.width <= 1 && self.height <= 1 {
+            return None;
+        }
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let pixel = |x: u32, y: u32| {
+            self.pixels[(y.min(self.height - 1) * self.width + x.min(self.width - 1)) as usize]
+        };
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let top = pixel(2 * x, 2 * y).lerp(&pixel(2 * x + 1, 2 * y), 0.5);
+                let bottom = pixel(2 * x, 2 * y + 1).lerp(&pixel(2 * x + 1, 2 * y + 1), 0.5);
+                top.lerp(&bottom, 0.5)
+            })
+            .collect();
+        Some(Self {
             pixels,
             width,
             height,
+            mips: Vec::new(),
         })
     }
 
-    pub fn get_line(&self, x: u8, line_target_height: u32) -> Vec<Color> {
-        if line_target_height == 0 {
-            return Vec::new();
+    /// Copy of a rectangle of the texture, clamped to its bounds
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+        let width = width.clamp(1, self.width - x);
+        let height = height.clamp(1, self.height - y);
+        let pixels = (y..y + height)
+            .flat_map(|row| {
+                let start = (row * self.width + x) as usize;
+                self.pixels[start..start + width as usize].iter().copied()
+            })
+            .collect();
+        Self::new(pixels, width, height)
+    }
+
+    /// Mip level showing about one texel per pixel when the full size
+    /// texture would show `texels_per_pixel`
+    pub fn mip(&self, texels_per_pixel: f32) -> &Texture {
+        let level = texels_per_pixel.max(1.0).log2() as usize;
+        match level.checked_sub(1) {
+            None => self,
+            Some(index) => self.mips.get(index).or(self.mips.last()).unwrap_or(self),
         }
-        let x = (x as f32 / 255.0 * self.width as f32) as u32;
-        let line = (0..self.height)
-            .map(|i| self.pixels[(i * self.width + x) as usize])
-            .collect::<Vec<Color>>();
-        (0..line_target_height)
-            .map(|i| line[(i * self.height / line_target_height) as usize])
-            .collect()
+    }
+
+    /// Mean color of the pixels
+    pub fn average(&self) -> Color {
+        let count = self.pixels.len().max(1) as f32;
+        let [r, g, b, a] = self
+            .pixels
+            .iter()
+            .fold([0.0; 4], |[r, g, b, a], pixel| {
+                [
+                    r + pixel.r as f32,
+                    g + pixel.g as f32,
+                    b + pixel.b as f32,
+                    a + pixel.a as f32,
+                ]
+            })
+            .map(|sum| (sum / count).round() as u8);
+        Color::new(r, g, b, a)
+    }
+
+    /// Nearest pixel to texture coordinates between 0 and 1
+    pub fn get_pixel(&self, u: f32, v: f32) -> Color {
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Color at texture coordinates between 0 and 1, the texture wrapping
+    /// around its edges when filtered
+    pub fn sample(&self, u: f32, v: f32, filter: TextureFilter) -> Color {
+        match filter {
+            TextureFilter::Nearest => self.get_pixel(u, v),
+            TextureFilter::Bilinear => {
+                let x = u * self.width as f32 - 0.5;
+                let y = v * self.height as f32 - 0.5;
+                let pixel = |x: f32, y: f32| {
+                    let x = (x as i64).rem_euclid(self.width as i64) as u32;
+                    let y = (y as i64).rem_euclid(self.height as i64) as u32;
+                    self.pixels[(y * self.width + x) as usize]
+                };
+                let (x0, y0) = (x.floor(), y.floor());
+                let top = pixel(x0, y0).lerp(&pixel(x0 + 1.0, y0), x - x0);
+                let bottom = pixel(x0, y0 + 1.0).lerp(&pixel(x0 + 1.0, y0 + 1.0), x - x0);
+                top.lerp(&bottom, y - y0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mips() {
+        let texture = Texture::new(
+            [Color::BLACK, Color::WHITE, Color::WHITE, Color::BLACK].repeat(4),
+            4,
+            4,
+        );
+        assert_eq!(texture.mips.len(), 2);
+        assert_eq!(texture.mip(1.5).width, 4);
+        assert_eq!(texture.mip(2.0).width, 2);
+        assert_eq!(texture.mip(100.0).width, 1);
+        assert_eq!(
+            texture.mip(100.0).pixels,
+            vec![Color::new(128, 128, 128, 255)]
+        );
+    }
+
+    #[test]
+    fn test_crop() {
+        let texture = Texture::new(
+            vec![Color::RED, Color::GREEN, Color::BLUE, Color::BLACK],
+            2,
+            2,
+        );
+        let cropped = texture.crop(1, 0, 1, 2);
+        assert_eq!((cropped.width, cropped.height), (1, 2));
+        assert_eq!(cropped.pixels, vec![Color::GREEN, Color::BLACK]);
+        assert_eq!(texture.crop(1, 1, 5, 5).pixels, vec![Color::BLACK]);
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let texture = Texture::new(vec![Color::BLACK, Color::WHITE], 2, 1);
+        assert_eq!(
+            texture.sample(0.25, 0.5, TextureFilter::Bilinear),
+            Color::BLACK
+        );
+        assert_eq!(
+            texture.sample(0.5, 0.5, TextureFilter::Bilinear),
+            Color::new(128, 128, 128, 255)
+        );
+        // Wraps around the edges
+        assert_eq!(
+            texture.sample(0.0, 0.5, TextureFilter::Bilinear),
+            Color::new(128, 128, 128, 255)
+        );
+        assert_eq!(
+            texture.sample(0.5, 0.5, TextureFilter::Nearest),
+            Color::WHITE
+        );
     }
 }